@@ -0,0 +1,61 @@
+use clparse::ChangelogParser;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::fmt::Write;
+
+const CHANGE_TYPES: &[&str] = &["Added", "Changed", "Deprecated", "Removed", "Fixed", "Security"];
+
+/// Generates a markdown changelog with `releases` releases, each with two
+/// entries of every change type.
+fn changelog(releases: usize) -> String {
+    let mut markdown = String::from(
+        "# Changelog\nAll notable changes to this project will be documented in this file.\n\n",
+    );
+
+    for release in (0..releases).rev() {
+        writeln!(markdown, "## [{}.0.0] - 2019-06-20", release).unwrap();
+
+        for change_type in CHANGE_TYPES {
+            writeln!(markdown, "### {}", change_type).unwrap();
+            for entry in 0..2 {
+                writeln!(
+                    markdown,
+                    "- {} entry {} of release {} with `code`, _emphasis_ and a [link](https://example.com) that is long enough to wrap",
+                    change_type, entry, release
+                )
+                .unwrap();
+            }
+            markdown.push('\n');
+        }
+    }
+
+    for release in (0..releases).rev() {
+        writeln!(markdown, "[{0}.0.0]: https://example.com/{0}.0.0", release).unwrap();
+    }
+
+    markdown
+}
+
+fn parse(c: &mut Criterion) {
+    let parser = ChangelogParser::default();
+    let markdown = changelog(1000);
+
+    c.bench_function("parse markdown", |b| {
+        b.iter(|| parser.parse_str(black_box(&markdown)).unwrap())
+    });
+    c.bench_function("parse markdown head", |b| {
+        b.iter(|| parser.parse_head(black_box(&markdown), 2).unwrap())
+    });
+}
+
+fn render(c: &mut Criterion) {
+    let parser = ChangelogParser::default();
+    let changelog = parser.parse_buffer(changelog(1000)).unwrap();
+
+    c.bench_function("render markdown", |b| b.iter(|| black_box(&changelog).to_string()));
+    c.bench_function("render json", |b| {
+        b.iter(|| serde_json::to_string_pretty(black_box(&changelog)).unwrap())
+    });
+}
+
+criterion_group!(benches, parse, render);
+criterion_main!(benches);
@@ -1,24 +1,34 @@
+use crate::conventional::Conventional;
+use crate::debian::Debian;
+use crate::feed::{Atom, Rss};
+use crate::markup::{AsciiDoc, Rst};
+use crate::render::{LineEndingWriter, Markdown, RenderOptions};
+use crate::rpm::Rpm;
+use crate::spec::SpecVersion;
+use crate::text::Text;
+use crate::OutputFormat;
 use anyhow::Result;
 use chrono::NaiveDate;
 use derive_builder::Builder;
 use derive_getters::Getters;
 use err_derive::Error;
-use indexmap::indexmap;
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
 use versions::Version;
-use serde::{ser::Serializer, Deserialize as _};
+use serde::{de::Error as _, ser::Serializer, Deserialize as _};
 use serde_derive::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fmt;
-use textwrap::wrap;
+use std::io::Write;
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
-pub enum Change {
-    Added(String),
-    Changed(String),
-    Deprecated(String),
-    Removed(String),
-    Fixed(String),
-    Security(String),
+pub enum Change<'a> {
+    Added(#[serde(borrow)] Cow<'a, str>),
+    Changed(#[serde(borrow)] Cow<'a, str>),
+    Deprecated(#[serde(borrow)] Cow<'a, str>),
+    Removed(#[serde(borrow)] Cow<'a, str>),
+    Fixed(#[serde(borrow)] Cow<'a, str>),
+    Security(#[serde(borrow)] Cow<'a, str>),
 }
 
 #[derive(Debug, Error)]
@@ -27,6 +37,52 @@ pub enum ChangeError {
     InvalidChangeType(String),
 }
 
+#[derive(Debug, Error)]
+pub enum ChangelogError {
+    #[error(display = "invalid version specified: {}", _0)]
+    InvalidVersion(String),
+    #[error(display = "no release found for version: {}", _0)]
+    ReleaseNotFound(String),
+}
+
+/// A problem found in a changelog that does not prevent it from being parsed.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum Diagnostic {
+    #[error(display = "unable to parse the version of release: {}", _0)]
+    InvalidVersion(String),
+    #[error(display = "release {} has no date", _0)]
+    MissingDate(String),
+    #[error(display = "release {} is listed after an older release", _0)]
+    UnorderedRelease(String),
+    #[error(display = "the unreleased section is marked as yanked, only releases can be yanked")]
+    YankedUnreleased,
+}
+
+impl Diagnostic {
+    /// The names of the lint rules, as used to allow them in the `[lint]`
+    /// section of the project configuration.
+    pub const RULES: &'static [&'static str] = &[
+        "invalid-version",
+        "missing-date",
+        "unordered-release",
+        "yanked-unreleased",
+    ];
+
+    /// The name of the lint rule reporting this diagnostic.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            Diagnostic::InvalidVersion(_) => "invalid-version",
+            Diagnostic::MissingDate(_) => "missing-date",
+            Diagnostic::UnorderedRelease(_) => "unordered-release",
+            Diagnostic::YankedUnreleased => "yanked-unreleased",
+        }
+    }
+}
+
+fn owned(text: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(text.into_owned())
+}
+
 fn version_serialize<S>(x: &Option<Version>, s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -42,38 +98,49 @@ where
     D: serde::Deserializer<'de>,
 {
     let s: Option<String> = Option::deserialize(deserializer)?;
-    Ok(s.and_then(|s| Version::new(&s)))
+
+    match s {
+        Some(s) => match Version::new(&s) {
+            Some(version) => Ok(Some(version)),
+            None => Err(D::Error::custom(format!("invalid version: {}", s))),
+        },
+        None => Ok(None),
+    }
 }
 
-#[derive(Debug, Clone, Builder, Getters, Serialize, Deserialize, PartialEq)]
-pub struct Release {
+#[derive(Debug, Clone, Builder, Getters, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Release<'a> {
     #[builder(setter(strip_option), default)]
     #[serde(
         serialize_with = "version_serialize",
         deserialize_with = "version_deserialize"
     )]
+    #[schemars(with = "Option<String>")]
     version: Option<Version>,
+    /// The original text of a release version that could not be parsed. A
+    /// release with an invalid version is never considered unreleased.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(setter(strip_option, into), default)]
+    invalid_version: Option<Cow<'a, str>>,
     #[builder(setter(strip_option, into), default)]
-    link: Option<String>,
+    link: Option<Cow<'a, str>>,
     #[builder(setter(strip_option), default)]
     date: Option<NaiveDate>,
+    #[serde(borrow)]
     #[builder(default)]
-    changes: Vec<Change>,
+    changes: Vec<Change<'a>>,
     #[builder(default = "false")]
     yanked: bool,
-    #[serde(skip, default = "default_separator")]
-    #[builder(default = "default_separator()")]
-    separator: String,
-    #[serde(skip)]
-    #[builder(default = "80.into()")]
-    wrap: Option<usize>,
-}
-
-fn default_separator() -> String {
-    "-".into()
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(setter(strip_option, into), default)]
+    yank_reason: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    labels: Vec<Cow<'a, str>>,
 }
 
-impl Release {
+impl<'a> Release<'a> {
     pub fn version_mut(&mut self) -> &mut Option<Version> {
         &mut self.version
     }
@@ -83,12 +150,18 @@ impl Release {
         self
     }
 
-    pub fn link_mut(&mut self) -> &mut Option<String> {
+    /// Whether this release is the unreleased section, i.e. has no version and
+    /// no unparseable version.
+    pub fn is_unreleased(&self) -> bool {
+        self.version.is_none() && self.invalid_version.is_none()
+    }
+
+    pub fn link_mut(&mut self) -> &mut Option<Cow<'a, str>> {
         &mut self.link
     }
 
-    pub fn set_link(&mut self, link: String) -> &mut Self {
-        self.link = Some(link);
+    pub fn set_link(&mut self, link: impl Into<Cow<'a, str>>) -> &mut Self {
+        self.link = Some(link.into());
         self
     }
 
@@ -101,60 +174,293 @@ impl Release {
         self
     }
 
-    pub fn changes_mut(&mut self) -> &mut Vec<Change> {
+    pub fn changes_mut(&mut self) -> &mut Vec<Change<'a>> {
         &mut self.changes
     }
 
-    pub fn set_changes(&mut self, changes: Vec<Change>) -> &mut Self {
+    pub fn set_changes(&mut self, changes: Vec<Change<'a>>) -> &mut Self {
         self.changes = changes;
         self
     }
 
+    pub fn labels_mut(&mut self) -> &mut Vec<Cow<'a, str>> {
+        &mut self.labels
+    }
+
+    pub fn set_labels(&mut self, labels: Vec<Cow<'a, str>>) -> &mut Self {
+        self.labels = labels;
+        self
+    }
+
     pub fn yank(&mut self, yanked: bool) {
-        if !self.yanked && yanked {
-            self.link = None;
+        if !yanked {
+            self.yank_reason = None;
         }
 
         self.yanked = yanked;
     }
+
+    pub fn yank_reason_mut(&mut self) -> &mut Option<Cow<'a, str>> {
+        &mut self.yank_reason
+    }
+
+    pub fn set_yank_reason(&mut self, reason: impl Into<Cow<'a, str>>) -> &mut Self {
+        self.yank_reason = Some(reason.into());
+        self
+    }
+
+    /// Copies any text borrowed from the parsed buffer, so the release can
+    /// outlive it.
+    pub fn into_owned(self) -> Release<'static> {
+        Release {
+            version: self.version,
+            invalid_version: self.invalid_version.map(owned),
+            link: self.link.map(owned),
+            date: self.date,
+            changes: self.changes.into_iter().map(Change::into_owned).collect(),
+            yanked: self.yanked,
+            yank_reason: self.yank_reason.map(owned),
+            labels: self.labels.into_iter().map(owned).collect(),
+        }
+    }
+
+    /// Writes the release to `writer` in the given format. Formats of whole
+    /// documents, such as feeds, are written as a changelog of this release
+    /// alone.
+    pub fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        format: &OutputFormat,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        options.validate()?;
+
+        match format {
+            OutputFormat::Json
+            | OutputFormat::Yaml
+            | OutputFormat::Markdown
+            | OutputFormat::Text => (),
+            _ => {
+                let changelog = ChangelogBuilder::default()
+                    .title("")
+                    .description("")
+                    .releases(vec![self.clone()])
+                    .build()
+                    .map_err(anyhow::Error::msg)?;
+
+                return changelog.write_to(writer, format, options);
+            }
+        }
+
+        let mut writer = LineEndingWriter::new(writer, options.line_ending());
+        match format {
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, self)?;
+                writer.write_all(b"\n")?;
+            }
+            OutputFormat::Yaml => serde_yaml::to_writer(&mut writer, self)?,
+            OutputFormat::Text => write!(writer, "{}", Text::new(self, options))?,
+            _ => write!(writer, "{}", Markdown::new(self, options))?,
+        }
+
+        Ok(())
+    }
+}
+
+/// A footnote definition, e.g. `[^1]: Some note`.
+#[derive(Debug, Clone, Getters, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Footnote<'a> {
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+    #[serde(borrow)]
+    text: Cow<'a, str>,
+}
+
+impl<'a> Footnote<'a> {
+    pub fn new(name: impl Into<Cow<'a, str>>, text: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            name: name.into(),
+            text: text.into(),
+        }
+    }
+
+    pub fn into_owned(self) -> Footnote<'static> {
+        Footnote::new(owned(self.name), owned(self.text))
+    }
 }
 
-#[derive(Debug, Clone, Builder, Getters, Serialize, Deserialize)]
-pub struct Changelog {
+/// A parsed changelog.
+//
+// Text is borrowed from the parsed buffer where it appears there verbatim, and
+// `into_owned` copies it when the changelog has to outlive the buffer.
+#[derive(Debug, Clone, Builder, Getters, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Changelog<'a> {
+    #[serde(borrow)]
     #[builder(setter(into))]
-    title: String,
+    title: Cow<'a, str>,
+    #[serde(borrow)]
     #[builder(setter(into))]
-    description: String,
+    description: Cow<'a, str>,
+    #[serde(borrow)]
     #[builder(default)]
-    releases: Vec<Release>,
+    releases: Vec<Release<'a>>,
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    footnotes: Vec<Footnote<'a>>,
 }
 
-impl Changelog {
-    pub fn unreleased_changes(&self) -> Vec<Change> {
+impl<'a> Changelog<'a> {
+    /// The JSON Schema of the JSON and YAML representations of a changelog.
+    pub fn json_schema() -> RootSchema {
+        schema_for!(Changelog<'static>)
+    }
+
+    pub fn releases_mut(&mut self) -> &mut Vec<Release<'a>> {
+        &mut self.releases
+    }
+
+    pub fn footnotes_mut(&mut self) -> &mut Vec<Footnote<'a>> {
+        &mut self.footnotes
+    }
+
+    pub fn into_releases(self) -> Vec<Release<'a>> {
+        self.releases
+    }
+
+    /// Copies any text borrowed from the parsed buffer, so the changelog can
+    /// outlive it.
+    pub fn into_owned(self) -> Changelog<'static> {
+        Changelog {
+            title: owned(self.title),
+            description: owned(self.description),
+            releases: self.releases.into_iter().map(Release::into_owned).collect(),
+            footnotes: self.footnotes.into_iter().map(Footnote::into_owned).collect(),
+        }
+    }
+
+    pub fn unreleased_changes(&self) -> Vec<Change<'a>> {
         self.releases
-            .clone()
-            .into_iter()
-            .filter(|r| r.version.is_none())
-            .map(|r| r.changes.clone())
-            .flatten()
+            .iter()
+            .filter(|r| r.is_unreleased())
+            .flat_map(|r| r.changes.iter().cloned())
             .collect()
     }
 
-    pub fn unreleased_mut(&mut self) -> Option<&mut Release> {
-        self.releases.iter_mut().find(|r| r.version == None)
+    pub fn unreleased(&self) -> Option<&Release<'a>> {
+        self.releases.iter().find(|r| r.is_unreleased())
     }
 
-    pub fn release_mut(&mut self, release: Version) -> Option<&mut Release> {
+    /// The first release with a version, usually the latest.
+    pub fn latest_release(&self) -> Option<&Release<'a>> {
+        self.releases.iter().find(|r| r.version.is_some())
+    }
+
+    pub fn release(&self, release: Version) -> Option<&Release<'a>> {
+        self.releases
+            .iter()
+            .find(|r| r.version == Some(release.clone()))
+    }
+
+    pub fn unreleased_mut(&mut self) -> Option<&mut Release<'a>> {
+        self.releases.iter_mut().find(|r| r.is_unreleased())
+    }
+
+    /// The version of the Keep a Changelog spec the description declares.
+    pub fn spec_version(&self) -> Option<SpecVersion> {
+        SpecVersion::detect(&self.description)
+    }
+
+    /// Problems found in the changelog, such as releases whose version could
+    /// not be parsed.
+    ///
+    /// Changelogs declaring a version of the Keep a Changelog spec are also
+    /// checked against it: releases must be dated and listed newest first, and
+    /// from 1.1.0 on the unreleased section can't be yanked.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = self
+            .releases
+            .iter()
+            .filter_map(|r| r.invalid_version.as_ref())
+            .map(|version| Diagnostic::InvalidVersion(version.to_string()))
+            .collect();
+
+        let spec = match self.spec_version() {
+            Some(spec) => spec,
+            None => return diagnostics,
+        };
+
+        let mut previous: Option<&Version> = None;
+        for release in &self.releases {
+            let version = match &release.version {
+                Some(version) => version,
+                None => {
+                    let yanked = release.yanked && release.is_unreleased();
+                    if yanked && spec.forbids_yanked_unreleased() {
+                        diagnostics.push(Diagnostic::YankedUnreleased);
+                    }
+                    continue;
+                }
+            };
+
+            if release.date.is_none() {
+                diagnostics.push(Diagnostic::MissingDate(version.to_string()));
+            }
+
+            if previous.is_some_and(|previous| version > previous) {
+                diagnostics.push(Diagnostic::UnorderedRelease(version.to_string()));
+            }
+            previous = Some(version);
+        }
+
+        diagnostics
+    }
+
+    pub fn release_mut(&mut self, release: Version) -> Option<&mut Release<'a>> {
         self.releases
             .iter_mut()
             .find(|r| r.version == Some(release.clone()))
     }
+
+    /// Writes the changelog to `writer` in the given format.
+    pub fn write_to<W: Write>(
+        &self,
+        writer: &mut W,
+        format: &OutputFormat,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        options.validate()?;
+        let mut writer = LineEndingWriter::new(writer, options.line_ending());
+
+        match format {
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, self)?;
+                writer.write_all(b"\n")?;
+            }
+            OutputFormat::Yaml => serde_yaml::to_writer(&mut writer, self)?,
+            OutputFormat::Markdown => write!(writer, "{}", Markdown::new(self, options))?,
+            OutputFormat::Debian => write!(writer, "{}", Debian::new(self, options)?)?,
+            OutputFormat::Rpm => write!(writer, "{}", Rpm::new(self, options)?)?,
+            OutputFormat::Atom => write!(writer, "{}", Atom::new(self, options)?)?,
+            OutputFormat::Rss => write!(writer, "{}", Rss::new(self, options)?)?,
+            OutputFormat::AsciiDoc => write!(writer, "{}", AsciiDoc::new(self, options))?,
+            OutputFormat::Rst => write!(writer, "{}", Rst::new(self, options))?,
+            OutputFormat::Text => write!(writer, "{}", Text::new(self, options))?,
+            OutputFormat::Conventional => {
+                write!(writer, "{}", Conventional::new(self, options))?
+            }
+        }
+
+        Ok(())
+    }
 }
 
-impl Change {
-    pub fn new(change_type: &str, description: String) -> Result<Self> {
+impl<'a> Change<'a> {
+    pub fn new(change_type: &str, description: impl Into<Cow<'a, str>>) -> Result<Self> {
         use self::Change::*;
 
+        let description = description.into();
         match change_type.to_lowercase().as_str() {
             "added" => Ok(Added(description)),
             "changed" => Ok(Changed(description)),
@@ -165,136 +471,62 @@ impl Change {
             _ => Err(ChangeError::InvalidChangeType(change_type.to_string()).into()),
         }
     }
-}
 
-impl fmt::Display for Change {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+    pub fn into_owned(self) -> Change<'static> {
+        use self::Change::*;
+
+        match self {
+            Added(description) => Added(owned(description)),
+            Changed(description) => Changed(owned(description)),
+            Deprecated(description) => Deprecated(owned(description)),
+            Removed(description) => Removed(owned(description)),
+            Fixed(description) => Fixed(owned(description)),
+            Security(description) => Security(owned(description)),
+        }
+    }
+
+    /// The name of the section this change belongs to, e.g. `Added`.
+    pub fn kind(&self) -> &'static str {
         use self::Change::*;
 
-        let description = match self {
+        match self {
+            Added(_) => "Added",
+            Changed(_) => "Changed",
+            Deprecated(_) => "Deprecated",
+            Removed(_) => "Removed",
+            Fixed(_) => "Fixed",
+            Security(_) => "Security",
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        use self::Change::*;
+
+        match self {
             Added(description) => description,
             Changed(description) => description,
             Deprecated(description) => description,
             Removed(description) => description,
             Fixed(description) => description,
             Security(description) => description,
-        };
-
-        fmt.write_str(&format!("- {}\n", description))?;
-
-        Ok(())
+        }
     }
 }
 
-impl fmt::Display for Release {
+impl fmt::Display for Change<'_> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        use self::Change::*;
-
-        // Release Heading.
-        fmt.write_str("## ")?;
-
-        // Release Version.
-        if let (Some(version), Some(date)) = (self.version.as_ref(), self.date) {
-            if self.yanked {
-                fmt.write_str(&format!("{} {} {} [YANKED]\n", version, self.separator, date))?;
-            } else {
-                fmt.write_str(&format!("[{}] {} {}\n", version, self.separator, date))?;
-            }
-        } else {
-            fmt.write_str("[Unreleased]\n")?;
-
-            if self.changes.is_empty() {
-                fmt.write_str("\n")?;
-            }
-        }
-
-        // Release changes.
-        let mut changes = self.changes.clone();
-
-        // If wrapping is enabled, we regenerate the list of changes and wrap
-        // them.
-        if let Some(wrap_at) = self.wrap {
-            changes = changes.into_iter().map(|change| {
-                let (change_type, mut description) = match change {
-                    Added(description) => ("added", description),
-                    Changed(description) => ("changed", description),
-                    Deprecated(description) => ("deprecated", description),
-                    Removed(description) => ("removed", description),
-                    Fixed(description) => ("fixed", description),
-                    Security(description) => ("security", description),
-                };
-
-                description = description.replace("\n", " ");
-                // The first 3 characters are not included in this change description,
-                // so we need to wrap at 3 less characters than expected.
-                description = wrap(&description, wrap_at - 3).join("\n  ");
-
-                Change::new(change_type, description.to_string()).unwrap()
-            }).collect();
-        }
-
-        let mut changesets = indexmap! {
-            "Added" => Vec::new(),
-            "Changed" => Vec::new(),
-            "Deprecated" => Vec::new(),
-            "Removed" => Vec::new(),
-            "Fixed" => Vec::new(),
-            "Security" => Vec::new(),
-        };
-        changes.iter().for_each(|change| match change {
-            Added(_) => changesets.get_mut("Added").unwrap().push(change),
-            Changed(_) => changesets.get_mut("Changed").unwrap().push(change),
-            Deprecated(_) => changesets.get_mut("Deprecated").unwrap().push(change),
-            Removed(_) => changesets.get_mut("Removed").unwrap().push(change),
-            Fixed(_) => changesets.get_mut("Fixed").unwrap().push(change),
-            Security(_) => changesets.get_mut("Security").unwrap().push(change),
-        });
-
-        changesets = changesets
-            .into_iter()
-            .filter(|(_, changes)| changes.clone().iter().count() > 0)
-            .collect();
-
-        for (name, changes) in changesets {
-            fmt.write_str(&format!("### {}\n", name))?;
-
-            for change in changes {
-                fmt.write_str(&change.to_string())?;
-            }
-
-            fmt.write_str("\n")?;
-        }
-
-        Ok(())
+        writeln!(fmt, "- {}", self.description())
     }
 }
 
-impl fmt::Display for Changelog {
+impl fmt::Display for Release<'_> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        fmt.write_str(&format!("# {}\n", self.title))?;
-        fmt.write_str(&self.description)?;
-
-        let mut links: Vec<(Version, String)> = Vec::new();
-        for release in self.releases.clone() {
-            fmt.write_str(&release.to_string())?;
-
-            if let (Some(version), Some(link)) = (release.version, release.link) {
-                links.push((version, link));
-            }
-        }
-
-        links.sort_by(|(a, _), (b, _)| b.cmp(a));
-
-        if let Some(release) = self.releases.clone().first() {
-            if let (None, Some(link)) = (release.version.as_ref(), release.link.as_ref()) {
-                fmt.write_str(&format!("[Unreleased]: {}\n", link))?
-            }
-        }
-
-        for (version, link) in links {
-            fmt.write_str(&format!("[{}]: {}\n", version, link))?;
-        }
+        write!(fmt, "{}", Markdown::new(self, &RenderOptions::default()))
+    }
+}
 
-        Ok(())
+impl fmt::Display for Changelog<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", Markdown::new(self, &RenderOptions::default()))
     }
 }
@@ -0,0 +1,413 @@
+use crate::changelog::Diagnostic;
+use crate::debian::Urgency;
+use crate::locale::Locale;
+use crate::render::{
+    EmphasisStyle, HeadingStyle, LineEnding, LinkStyle, Wrap, DEFAULT_SECTION_ORDER,
+};
+use crate::OutputFormat;
+use anyhow::Result;
+use derive_builder::Builder;
+use derive_getters::Getters;
+use err_derive::Error;
+use serde_derive::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the project configuration file.
+pub const CONFIG_FILE_NAME: &str = ".clparse.toml";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error(display = "unknown section in configuration: {}", _0)]
+    UnknownSection(String),
+    #[error(display = "unknown locale: {}", _0)]
+    UnknownLocale(String),
+    #[error(display = "unknown lint rule in configuration: {}", _0)]
+    UnknownLintRule(String),
+}
+
+/// The `wrap` option, either a boolean or `"preserve"`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum WrapOption {
+    Enabled(bool),
+    Mode(WrapMode),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapMode {
+    /// Keep the line breaks change entries were written with.
+    Preserve,
+}
+
+/// Project configuration, usually read from a `.clparse.toml` file.
+///
+/// Every option is optional. Unset options fall back to the defaults of the
+/// CLI, and options passed on the command line take precedence over the file.
+#[derive(Debug, Clone, Default, Builder, Getters, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    #[builder(setter(strip_option, into), default)]
+    separator: Option<String>,
+    #[builder(setter(strip_option), default)]
+    heading_separators: Option<Vec<String>>,
+    #[builder(setter(strip_option), default)]
+    date_formats: Option<Vec<String>>,
+    #[builder(setter(strip_option), default)]
+    wrap: Option<WrapOption>,
+    #[builder(setter(strip_option), default)]
+    wrap_at: Option<usize>,
+    #[builder(setter(strip_option), default)]
+    format: Option<OutputFormat>,
+    #[builder(setter(strip_option), default)]
+    section_order: Option<Vec<String>>,
+    #[builder(setter(strip_option), default)]
+    preserve_section_order: Option<bool>,
+    #[builder(setter(strip_option), default)]
+    section_names: Option<HashMap<String, String>>,
+    #[builder(setter(strip_option), default)]
+    section_aliases: Option<HashMap<String, Vec<String>>>,
+    #[builder(setter(strip_option), default)]
+    link_style: Option<LinkStyle>,
+    #[builder(setter(strip_option), default)]
+    line_ending: Option<LineEnding>,
+    #[builder(setter(strip_option), default)]
+    heading_style: Option<HeadingStyle>,
+    #[builder(setter(strip_option, into), default)]
+    output_date_format: Option<String>,
+    #[builder(setter(strip_option), default)]
+    bullet: Option<char>,
+    #[builder(setter(strip_option), default)]
+    emphasis: Option<EmphasisStyle>,
+    #[builder(setter(strip_option, into), default)]
+    locale: Option<String>,
+    #[builder(setter(strip_option, into), default)]
+    repo_url: Option<String>,
+    #[builder(setter(strip_option), default)]
+    lint: Option<LintConfig>,
+    #[builder(setter(strip_option), default)]
+    debian: Option<DebianConfig>,
+    #[builder(setter(strip_option), default)]
+    rpm: Option<RpmConfig>,
+    #[builder(setter(strip_option), default)]
+    feed: Option<FeedConfig>,
+    #[builder(setter(strip_option), default)]
+    import: Option<ImportConfig>,
+    #[builder(setter(strip_option), default)]
+    conventional: Option<ConventionalConfig>,
+}
+
+/// The `[lint]` section of the project configuration.
+#[derive(Debug, Clone, Default, Builder, Getters, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LintConfig {
+    /// The lint rules that are not reported, e.g. `["missing-date"]`.
+    #[builder(setter(strip_option), default)]
+    allow: Option<Vec<String>>,
+}
+
+impl LintConfig {
+    /// Whether a diagnostic is reported, i.e. its rule is not allowed.
+    pub fn reports(&self, diagnostic: &Diagnostic) -> bool {
+        !self
+            .allow
+            .iter()
+            .flatten()
+            .any(|rule| rule == diagnostic.rule())
+    }
+}
+
+/// The `[debian]` section of the project configuration.
+#[derive(Debug, Clone, Default, Builder, Getters, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DebianConfig {
+    #[builder(setter(strip_option, into), default)]
+    package: Option<String>,
+    #[builder(setter(strip_option, into), default)]
+    distribution: Option<String>,
+    #[builder(setter(strip_option, into), default)]
+    maintainer: Option<String>,
+    #[builder(setter(strip_option, into), default)]
+    revision: Option<String>,
+    #[builder(setter(strip_option), default)]
+    urgency: Option<Urgency>,
+    #[builder(setter(strip_option), default)]
+    section_urgencies: Option<HashMap<String, Urgency>>,
+}
+
+impl DebianConfig {
+    pub fn set_maintainer(&mut self, maintainer: String) -> &mut Self {
+        self.maintainer = Some(maintainer);
+        self
+    }
+}
+
+/// The `[rpm]` section of the project configuration.
+#[derive(Debug, Clone, Default, Builder, Getters, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RpmConfig {
+    #[builder(setter(strip_option, into), default)]
+    packager: Option<String>,
+    #[builder(setter(strip_option, into), default)]
+    release: Option<String>,
+}
+
+impl RpmConfig {
+    pub fn set_packager(&mut self, packager: String) -> &mut Self {
+        self.packager = Some(packager);
+        self
+    }
+}
+
+/// The `[feed]` section of the project configuration.
+#[derive(Debug, Clone, Default, Builder, Getters, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FeedConfig {
+    #[builder(setter(strip_option, into), default)]
+    title: Option<String>,
+    #[builder(setter(strip_option, into), default)]
+    link: Option<String>,
+    #[builder(setter(strip_option, into), default)]
+    author: Option<String>,
+    #[builder(setter(strip_option, into), default)]
+    description: Option<String>,
+}
+
+/// The `[import]` section of the project configuration.
+#[derive(Debug, Clone, Default, Builder, Getters, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ImportConfig {
+    /// The change types the entries under other headings, or with other tags,
+    /// are imported as, e.g. `"Performance Improvements" = "Changed"`.
+    #[builder(setter(strip_option), default)]
+    headings: Option<HashMap<String, String>>,
+}
+
+/// The `[conventional]` section of the project configuration.
+#[derive(Debug, Clone, Default, Builder, Getters, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConventionalConfig {
+    #[builder(setter(strip_option), default)]
+    sections: Option<HashMap<String, String>>,
+}
+
+impl ImportConfig {
+    pub fn headings_mut(&mut self) -> &mut Option<HashMap<String, String>> {
+        &mut self.headings
+    }
+}
+
+impl Config {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let config: Config = toml::from_str(&fs::read_to_string(path)?)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Checks that the sections named in the configuration, and the change
+    /// types headings are imported as, are change types, and that the locale
+    /// and allowed lint rules are known.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(locale) = &self.locale {
+            if Locale::find(locale).is_none() {
+                return Err(ConfigError::UnknownLocale(locale.clone()));
+            }
+        }
+
+        let allowed = self.lint.iter().flat_map(|lint| lint.allow.iter().flatten());
+        for rule in allowed {
+            if !Diagnostic::RULES.contains(&rule.as_str()) {
+                return Err(ConfigError::UnknownLintRule(rule.clone()));
+            }
+        }
+
+        let order = self.section_order.iter().flatten();
+        let names = self.section_names.iter().flat_map(HashMap::keys);
+        let aliases = self.section_aliases.iter().flat_map(HashMap::keys);
+        let urgencies = self
+            .debian
+            .iter()
+            .flat_map(|debian| debian.section_urgencies.iter().flat_map(HashMap::keys));
+
+        let conventional = self
+            .conventional
+            .iter()
+            .flat_map(|conventional| conventional.sections.iter().flat_map(HashMap::keys));
+        let imported = self
+            .import
+            .iter()
+            .flat_map(|import| import.headings.iter().flat_map(HashMap::values));
+
+        for section in order.chain(names).chain(aliases).chain(urgencies).chain(conventional).chain(imported) {
+            let known = DEFAULT_SECTION_ORDER
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(section));
+
+            if !known {
+                return Err(ConfigError::UnknownSection(section.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Looks for a `.clparse.toml` file in `directory` and each of its
+    /// ancestors, returning the first one found along with its path.
+    pub fn discover(directory: &Path) -> Result<Option<(PathBuf, Self)>> {
+        for ancestor in directory.ancestors() {
+            let path = ancestor.join(CONFIG_FILE_NAME);
+
+            if path.is_file() {
+                let config = Self::from_path(&path)?;
+
+                return Ok(Some((path, config)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn set_separator(&mut self, separator: String) -> &mut Self {
+        self.separator = Some(separator);
+        self
+    }
+
+    pub fn set_heading_separators(&mut self, heading_separators: Vec<String>) -> &mut Self {
+        self.heading_separators = Some(heading_separators);
+        self
+    }
+
+    pub fn set_date_formats(&mut self, date_formats: Vec<String>) -> &mut Self {
+        self.date_formats = Some(date_formats);
+        self
+    }
+
+    pub fn set_wrap(&mut self, wrap: WrapOption) -> &mut Self {
+        self.wrap = Some(wrap);
+        self
+    }
+
+    pub fn set_wrap_at(&mut self, wrap_at: usize) -> &mut Self {
+        self.wrap_at = Some(wrap_at);
+        self
+    }
+
+    pub fn set_format(&mut self, format: OutputFormat) -> &mut Self {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn set_section_order(&mut self, section_order: Vec<String>) -> &mut Self {
+        self.section_order = Some(section_order);
+        self
+    }
+
+    pub fn set_preserve_section_order(&mut self, preserve_section_order: bool) -> &mut Self {
+        self.preserve_section_order = Some(preserve_section_order);
+        self
+    }
+
+    pub fn set_section_names(&mut self, section_names: HashMap<String, String>) -> &mut Self {
+        self.section_names = Some(section_names);
+        self
+    }
+
+    pub fn set_section_aliases(&mut self, section_aliases: HashMap<String, Vec<String>>) -> &mut Self {
+        self.section_aliases = Some(section_aliases);
+        self
+    }
+
+    pub fn set_link_style(&mut self, link_style: LinkStyle) -> &mut Self {
+        self.link_style = Some(link_style);
+        self
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) -> &mut Self {
+        self.line_ending = Some(line_ending);
+        self
+    }
+
+    pub fn set_heading_style(&mut self, heading_style: HeadingStyle) -> &mut Self {
+        self.heading_style = Some(heading_style);
+        self
+    }
+
+    pub fn set_output_date_format(&mut self, output_date_format: String) -> &mut Self {
+        self.output_date_format = Some(output_date_format);
+        self
+    }
+
+    pub fn set_bullet(&mut self, bullet: char) -> &mut Self {
+        self.bullet = Some(bullet);
+        self
+    }
+
+    pub fn set_emphasis(&mut self, emphasis: EmphasisStyle) -> &mut Self {
+        self.emphasis = Some(emphasis);
+        self
+    }
+
+    pub fn set_locale(&mut self, locale: String) -> &mut Self {
+        self.locale = Some(locale);
+        self
+    }
+
+    pub fn set_repo_url(&mut self, repo_url: String) -> &mut Self {
+        self.repo_url = Some(repo_url);
+        self
+    }
+
+    pub fn set_lint(&mut self, lint: LintConfig) -> &mut Self {
+        self.lint = Some(lint);
+        self
+    }
+
+    pub fn set_debian(&mut self, debian: DebianConfig) -> &mut Self {
+        self.debian = Some(debian);
+        self
+    }
+
+    pub fn debian_mut(&mut self) -> &mut Option<DebianConfig> {
+        &mut self.debian
+    }
+
+    pub fn set_rpm(&mut self, rpm: RpmConfig) -> &mut Self {
+        self.rpm = Some(rpm);
+        self
+    }
+
+    pub fn rpm_mut(&mut self) -> &mut Option<RpmConfig> {
+        &mut self.rpm
+    }
+
+    pub fn set_feed(&mut self, feed: FeedConfig) -> &mut Self {
+        self.feed = Some(feed);
+        self
+    }
+
+    pub fn set_import(&mut self, import: ImportConfig) -> &mut Self {
+        self.import = Some(import);
+        self
+    }
+
+    pub fn import_mut(&mut self) -> &mut Option<ImportConfig> {
+        &mut self.import
+    }
+
+    pub fn set_conventional(&mut self, conventional: ConventionalConfig) -> &mut Self {
+        self.conventional = Some(conventional);
+        self
+    }
+
+    /// How change entries are wrapped.
+    pub fn wrap_mode(&self) -> Wrap {
+        match self.wrap {
+            Some(WrapOption::Enabled(false)) => Wrap::Off,
+            Some(WrapOption::Mode(WrapMode::Preserve)) => Wrap::Preserve,
+            _ => Wrap::At(self.wrap_at.unwrap_or(80)),
+        }
+    }
+}
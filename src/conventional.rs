@@ -0,0 +1,172 @@
+use crate::changelog::{Changelog, Release};
+use crate::config::ConventionalConfig;
+use crate::render::RenderOptions;
+use crate::wrap::entry_lines;
+use derive_builder::Builder;
+use derive_getters::Getters;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The heading of the section of features.
+const FEATURES: &str = "Features";
+/// The heading of the section of bug fixes.
+const BUG_FIXES: &str = "Bug Fixes";
+/// The heading of the section of breaking changes.
+const BREAKING_CHANGES: &str = "BREAKING CHANGES";
+/// The heading of the section of other changes in existing functionality.
+const CHANGES: &str = "Changes";
+/// The heading of the section of deprecations.
+const DEPRECATIONS: &str = "Deprecations";
+
+/// Options for rendering a changelog as conventional-changelog Markdown.
+#[derive(Debug, Clone, Builder, Getters, PartialEq)]
+pub struct ConventionalOptions {
+    /// The heading of the section the changes of a change type are listed in,
+    /// keyed by change type.
+    #[builder(default = "default_sections()")]
+    sections: HashMap<String, String>,
+}
+
+fn default_sections() -> HashMap<String, String> {
+    [
+        ("added", FEATURES),
+        ("changed", CHANGES),
+        ("deprecated", DEPRECATIONS),
+        ("removed", BREAKING_CHANGES),
+        ("fixed", BUG_FIXES),
+        ("security", BUG_FIXES),
+    ]
+    .iter()
+    .map(|(kind, heading)| (kind.to_string(), heading.to_string()))
+    .collect()
+}
+
+impl Default for ConventionalOptions {
+    fn default() -> Self {
+        ConventionalOptionsBuilder::default().build().unwrap()
+    }
+}
+
+impl ConventionalOptions {
+    /// Creates conventional-changelog options from the `[conventional]`
+    /// section of a project configuration, using the defaults for any that
+    /// are unset.
+    pub fn from_config(config: &ConventionalConfig) -> Self {
+        let mut options = Self::default();
+
+        // Configured change types are lowercased before they override the
+        // defaults, so `Changed` and `changed` are the same one.
+        if let Some(sections) = config.sections() {
+            let sections = sections
+                .iter()
+                .map(|(kind, heading)| (kind.to_lowercase(), heading.clone()));
+            options.sections.extend(sections);
+        }
+
+        options
+    }
+
+    /// Sets the heading of the section the changes of a change type are
+    /// listed in, keyed by change type regardless of case.
+    pub fn set_sections(&mut self, sections: HashMap<String, String>) -> &mut Self {
+        self.sections = sections
+            .into_iter()
+            .map(|(kind, heading)| (kind.to_lowercase(), heading))
+            .collect();
+        self
+    }
+
+    /// The headings of the sections of a release, features and bug fixes
+    /// first and breaking changes last, as conventional-changelog orders them,
+    /// with any others in between.
+    fn headings<'a>(&'a self, release: &Release, options: &RenderOptions) -> Vec<&'a str> {
+        let mut headings: Vec<&str> = Vec::new();
+
+        for section in options.sections(release) {
+            if !release.changes().iter().any(|change| change.kind() == section) {
+                continue;
+            }
+
+            if let Some(heading) = self.sections.get(&section.to_lowercase()) {
+                if !headings.contains(&heading.as_str()) {
+                    headings.push(heading);
+                }
+            }
+        }
+
+        headings.sort_by_key(|heading| match *heading {
+            FEATURES => 0,
+            BUG_FIXES => 1,
+            BREAKING_CHANGES => 3,
+            _ => 2,
+        });
+
+        headings
+    }
+}
+
+/// Renders the versioned releases of a changelog as conventional-changelog
+/// Markdown.
+pub(crate) struct Conventional<'a> {
+    changelog: &'a Changelog<'a>,
+    options: &'a RenderOptions,
+}
+
+impl<'a> Conventional<'a> {
+    pub(crate) fn new(changelog: &'a Changelog<'a>, options: &'a RenderOptions) -> Self {
+        Self { changelog, options }
+    }
+}
+
+impl<'a> fmt::Display for Conventional<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let conventional = self.options.conventional();
+
+        // conventional-changelog has no unreleased section.
+        let releases = self.changelog.releases().iter();
+        let releases = releases.filter(|release| release.version().is_some());
+
+        for (index, release) in releases.enumerate() {
+            if index > 0 {
+                fmt.write_str("\n")?;
+            }
+
+            let version = release.version().as_ref().unwrap();
+            match release.link() {
+                Some(link) => write!(fmt, "# [{}]({})", version, link)?,
+                None => write!(fmt, "# {}", version)?,
+            }
+            if let Some(date) = release.date() {
+                write!(fmt, " ({})", date.format("%Y-%m-%d"))?;
+            }
+            fmt.write_str("\n")?;
+
+            for heading in conventional.headings(release, self.options) {
+                write!(fmt, "\n### {}\n\n", heading)?;
+
+                for section in self.options.sections(release) {
+                    let listed = conventional.sections.get(&section.to_lowercase());
+                    if listed.map(String::as_str) != Some(heading) {
+                        continue;
+                    }
+
+                    let changes = release.changes().iter();
+                    for change in changes.filter(|change| change.kind() == section) {
+                        let lines = entry_lines(change.description(), self.options.wrap());
+
+                        for (index, line) in lines.iter().enumerate() {
+                            match (index, line.is_empty()) {
+                                (0, _) => write!(fmt, "* {}", line)?,
+                                (_, true) => fmt.write_str("\n")?,
+                                (_, false) => write!(fmt, "\n  {}", line)?,
+                            }
+                        }
+                        fmt.write_str("\n")?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
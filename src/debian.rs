@@ -0,0 +1,277 @@
+use crate::changelog::{Changelog, Release};
+use crate::config::DebianConfig;
+use crate::render::RenderOptions;
+use crate::wrap::indented_entry_lines;
+use derive_builder::Builder;
+use derive_getters::Getters;
+use err_derive::Error;
+use serde_derive::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use versions::Version;
+
+/// The number of columns taken by the `  * ` bullet of a Debian change entry.
+const BULLET_WIDTH: usize = 4;
+
+#[derive(Debug, Error)]
+pub enum DebianError {
+    #[error(
+        display = "the debian package name is not set, set `package` in the [debian] section of the configuration"
+    )]
+    MissingPackage,
+    #[error(
+        display = "the debian maintainer is not set, set `maintainer` in the [debian] section of the configuration or the DEBFULLNAME and DEBEMAIL environment variables"
+    )]
+    MissingMaintainer,
+    #[error(display = "release {} has no date, which debian changelogs require", _0)]
+    MissingDate(String),
+}
+
+/// The urgency of a Debian upload, from lowest to highest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    Medium,
+    High,
+    Emergency,
+    Critical,
+}
+
+impl fmt::Display for Urgency {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            Urgency::Low => "low",
+            Urgency::Medium => "medium",
+            Urgency::High => "high",
+            Urgency::Emergency => "emergency",
+            Urgency::Critical => "critical",
+        })
+    }
+}
+
+/// Options for rendering a changelog as a `debian/changelog` file.
+#[derive(Debug, Clone, Builder, Getters, PartialEq)]
+pub struct DebianOptions {
+    /// The name of the source package.
+    #[builder(setter(strip_option, into), default)]
+    package: Option<String>,
+    #[builder(setter(into), default = "\"unstable\".into()")]
+    distribution: String,
+    /// The maintainer of the package, e.g. `Jane Doe <jane@example.com>`.
+    #[builder(setter(strip_option, into), default)]
+    maintainer: Option<String>,
+    /// The Debian revision appended to release versions, e.g. `1` for
+    /// `1.2.0-1`.
+    #[builder(setter(strip_option, into), default)]
+    revision: Option<String>,
+    /// The urgency of releases without changes in any of the sections of
+    /// `section_urgencies`.
+    #[builder(default = "Urgency::Medium")]
+    urgency: Urgency,
+    /// The urgency of releases with changes in a section, by change type. A
+    /// release gets the highest urgency of its sections.
+    #[builder(default = "default_section_urgencies()")]
+    section_urgencies: HashMap<String, Urgency>,
+}
+
+fn default_section_urgencies() -> HashMap<String, Urgency> {
+    let mut urgencies = HashMap::new();
+    urgencies.insert("security".to_string(), Urgency::High);
+
+    urgencies
+}
+
+impl Default for DebianOptions {
+    fn default() -> Self {
+        DebianOptionsBuilder::default().build().unwrap()
+    }
+}
+
+impl DebianOptions {
+    /// Creates Debian options from the `[debian]` section of a project
+    /// configuration, using the defaults for any that are unset.
+    pub fn from_config(config: &DebianConfig) -> Self {
+        let mut options = Self::default();
+
+        if let Some(package) = config.package() {
+            options.set_package(package.clone());
+        }
+
+        if let Some(distribution) = config.distribution() {
+            options.set_distribution(distribution.clone());
+        }
+
+        if let Some(maintainer) = config.maintainer() {
+            options.set_maintainer(maintainer.clone());
+        }
+
+        if let Some(revision) = config.revision() {
+            options.set_revision(revision.clone());
+        }
+
+        if let Some(urgency) = config.urgency() {
+            options.set_urgency(*urgency);
+        }
+
+        if let Some(section_urgencies) = config.section_urgencies() {
+            let mut urgencies = options.section_urgencies.clone();
+            urgencies.extend(section_urgencies.clone());
+            options.set_section_urgencies(urgencies);
+        }
+
+        options
+    }
+
+    pub fn set_package(&mut self, package: String) -> &mut Self {
+        self.package = Some(package);
+        self
+    }
+
+    pub fn set_distribution(&mut self, distribution: String) -> &mut Self {
+        self.distribution = distribution;
+        self
+    }
+
+    pub fn set_maintainer(&mut self, maintainer: String) -> &mut Self {
+        self.maintainer = Some(maintainer);
+        self
+    }
+
+    pub fn set_revision(&mut self, revision: String) -> &mut Self {
+        self.revision = Some(revision);
+        self
+    }
+
+    pub fn set_urgency(&mut self, urgency: Urgency) -> &mut Self {
+        self.urgency = urgency;
+        self
+    }
+
+    /// Sets the urgency of releases with changes in a section, keyed by change
+    /// type regardless of case.
+    pub fn set_section_urgencies(&mut self, section_urgencies: HashMap<String, Urgency>) -> &mut Self {
+        self.section_urgencies = section_urgencies
+            .into_iter()
+            .map(|(kind, urgency)| (kind.to_lowercase(), urgency))
+            .collect();
+        self
+    }
+
+    /// The urgency of a release, the highest of the default urgency and the
+    /// urgencies of the sections it has changes in.
+    pub fn release_urgency(&self, release: &Release) -> Urgency {
+        release
+            .changes()
+            .iter()
+            .filter_map(|change| self.section_urgencies.get(&change.kind().to_lowercase()))
+            .fold(self.urgency, |urgency, section| urgency.max(*section))
+    }
+
+    /// The Debian version of a release. Pre-release versions sort before the
+    /// release, e.g. `1.0.0~rc.1`.
+    fn version(&self, version: &Version) -> String {
+        let version = version.to_string().replacen('-', "~", 1);
+
+        match &self.revision {
+            Some(revision) => format!("{}-{}", version, revision),
+            None => version,
+        }
+    }
+}
+
+/// Renders the versioned releases of a changelog as `debian/changelog`
+/// stanzas.
+pub(crate) struct Debian<'a> {
+    changelog: &'a Changelog<'a>,
+    options: &'a RenderOptions,
+    package: &'a str,
+    maintainer: &'a str,
+}
+
+impl<'a> Debian<'a> {
+    /// Checks that the changelog can be rendered with the given options.
+    pub(crate) fn new(changelog: &'a Changelog, options: &'a RenderOptions) -> Result<Self, DebianError> {
+        let debian = options.debian();
+        let package = debian.package.as_deref().ok_or(DebianError::MissingPackage)?;
+        let maintainer = debian
+            .maintainer
+            .as_deref()
+            .ok_or(DebianError::MissingMaintainer)?;
+
+        for release in changelog.releases() {
+            if let (Some(version), None) = (release.version(), release.date()) {
+                return Err(DebianError::MissingDate(version.to_string()));
+            }
+        }
+
+        Ok(Self {
+            changelog,
+            options,
+            package,
+            maintainer,
+        })
+    }
+}
+
+impl<'a> fmt::Display for Debian<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let debian = self.options.debian();
+        let mut first = true;
+
+        for release in self.changelog.releases() {
+            // Unreleased sections and releases with invalid versions have no
+            // version to upload.
+            let (version, date) = match (release.version(), release.date()) {
+                (Some(version), Some(date)) => (version, date),
+                _ => continue,
+            };
+
+            if !first {
+                fmt.write_str("\n")?;
+            }
+            first = false;
+
+            writeln!(
+                fmt,
+                "{} ({}) {}; urgency={}",
+                self.package,
+                debian.version(version),
+                debian.distribution,
+                debian.release_urgency(release)
+            )?;
+            fmt.write_str("\n")?;
+
+            if release.changes().is_empty() {
+                fmt.write_str("  * New upstream release.\n")?;
+            }
+
+            for section in self.options.sections(release) {
+                let changes = release.changes().iter();
+
+                for change in changes.filter(|change| change.kind() == section) {
+                    let lines =
+                        indented_entry_lines(change.description(), self.options.wrap(), BULLET_WIDTH);
+
+                    for (index, line) in lines.iter().enumerate() {
+                        match (index, line.is_empty()) {
+                            (0, _) => writeln!(fmt, "  * {}", line)?,
+                            (_, true) => fmt.write_str("\n")?,
+                            (_, false) => writeln!(fmt, "    {}", line)?,
+                        }
+                    }
+                }
+            }
+
+            fmt.write_str("\n")?;
+            writeln!(
+                fmt,
+                " -- {}  {}",
+                self.maintainer,
+                date.format("%a, %d %b %Y 00:00:00 +0000")
+            )?;
+        }
+
+        Ok(())
+    }
+}
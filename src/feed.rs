@@ -0,0 +1,305 @@
+use crate::changelog::{Changelog, Release};
+use crate::config::FeedConfig;
+use crate::render::{Changes, RenderOptions};
+use chrono::NaiveDate;
+use derive_builder::Builder;
+use derive_getters::Getters;
+use err_derive::Error;
+use pulldown_cmark::{html, Options, Parser};
+use std::borrow::Cow;
+use std::fmt;
+
+#[derive(Debug, Error)]
+pub enum FeedError {
+    #[error(
+        display = "the feed link is not set, set `link` in the [feed] section of the configuration"
+    )]
+    MissingLink,
+    #[error(display = "an Atom feed needs a release with a date, which it was last updated on")]
+    MissingUpdated,
+}
+
+/// Options for rendering a changelog as an Atom or RSS feed.
+#[derive(Debug, Clone, Default, Builder, Getters, PartialEq)]
+pub struct FeedOptions {
+    /// The title of the feed. Defaults to the title of the changelog.
+    #[builder(setter(strip_option, into), default)]
+    title: Option<String>,
+    /// The URL of the project the feed is for, also used to identify releases
+    /// without a link.
+    #[builder(setter(strip_option, into), default)]
+    link: Option<String>,
+    /// The author of the releases. Defaults to the title of the feed.
+    #[builder(setter(strip_option, into), default)]
+    author: Option<String>,
+    /// The description of the feed. Defaults to the title of the feed in RSS
+    /// feeds, which require one.
+    #[builder(setter(strip_option, into), default)]
+    description: Option<String>,
+}
+
+impl FeedOptions {
+    /// Creates feed options from the `[feed]` section of a project
+    /// configuration.
+    pub fn from_config(config: &FeedConfig) -> Self {
+        let mut options = Self::default();
+
+        if let Some(title) = config.title() {
+            options.set_title(title.clone());
+        }
+
+        if let Some(link) = config.link() {
+            options.set_link(link.clone());
+        }
+
+        if let Some(author) = config.author() {
+            options.set_author(author.clone());
+        }
+
+        if let Some(description) = config.description() {
+            options.set_description(description.clone());
+        }
+
+        options
+    }
+
+    pub fn set_title(&mut self, title: String) -> &mut Self {
+        self.title = Some(title);
+        self
+    }
+
+    pub fn set_link(&mut self, link: String) -> &mut Self {
+        self.link = Some(link);
+        self
+    }
+
+    pub fn set_author(&mut self, author: String) -> &mut Self {
+        self.author = Some(author);
+        self
+    }
+
+    pub fn set_description(&mut self, description: String) -> &mut Self {
+        self.description = Some(description);
+        self
+    }
+}
+
+/// A release as an item of a feed.
+struct Item<'a> {
+    release: &'a Release<'a>,
+    title: String,
+    date: &'a NaiveDate,
+    /// The release link, or the feed link with the version as its fragment.
+    id: String,
+    /// The changes of the release as HTML.
+    content: String,
+}
+
+/// The parts of a changelog shared by Atom and RSS feeds.
+struct Feed<'a> {
+    title: &'a str,
+    link: &'a str,
+    author: &'a str,
+    description: Option<&'a str>,
+    items: Vec<Item<'a>>,
+}
+
+impl<'a> Feed<'a> {
+    /// Collects the items of a feed. Only versioned releases with a date are
+    /// published.
+    fn new(changelog: &'a Changelog<'a>, options: &'a RenderOptions) -> Result<Self, FeedError> {
+        let feed = options.feed();
+        let link = feed.link.as_deref().ok_or(FeedError::MissingLink)?;
+        let title = feed.title.as_deref().unwrap_or_else(|| changelog.title());
+        let author = feed.author.as_deref().unwrap_or(title);
+        let description = feed.description.as_deref();
+
+        let locale = options.locale();
+        let items = changelog
+            .releases()
+            .iter()
+            .filter_map(|release| {
+                let (version, date) = match (release.version(), release.date()) {
+                    (Some(version), Some(date)) => (version, date),
+                    _ => return None,
+                };
+
+                let title = if *release.yanked() {
+                    format!("{} [{}]", version, locale.yanked())
+                } else {
+                    version.to_string()
+                };
+                let id = match release.link() {
+                    Some(link) => link.to_string(),
+                    None => format!("{}#{}", link, version),
+                };
+
+                Some(Item {
+                    release,
+                    title,
+                    date,
+                    id,
+                    content: content(release, options),
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            title,
+            link,
+            author,
+            description,
+            items,
+        })
+    }
+}
+
+/// The changes of a release, and why it was yanked, as HTML.
+fn content(release: &Release, options: &RenderOptions) -> String {
+    let mut markdown = String::new();
+    if let (true, Some(reason)) = (*release.yanked(), release.yank_reason()) {
+        markdown.push_str(reason);
+        markdown.push_str("\n\n");
+    }
+    markdown.push_str(&Changes::new(release, options).to_string());
+
+    let mut content = String::new();
+    html::push_html(
+        &mut content,
+        Parser::new_ext(&markdown, Options::ENABLE_STRIKETHROUGH),
+    );
+
+    content
+}
+
+/// Escapes text for use in XML.
+fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    Cow::Owned(escaped)
+}
+
+/// Renders the releases of a changelog as an Atom feed.
+pub(crate) struct Atom<'a> {
+    feed: Feed<'a>,
+    /// The date of the latest release, which the feed was last updated on.
+    updated: &'a NaiveDate,
+}
+
+impl<'a> Atom<'a> {
+    /// Checks that the changelog can be rendered with the given options.
+    pub(crate) fn new(changelog: &'a Changelog, options: &'a RenderOptions) -> Result<Self, FeedError> {
+        let feed = Feed::new(changelog, options)?;
+        let updated = feed
+            .items
+            .iter()
+            .map(|item| item.date)
+            .max()
+            .ok_or(FeedError::MissingUpdated)?;
+
+        Ok(Self { feed, updated })
+    }
+}
+
+impl<'a> fmt::Display for Atom<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let feed = &self.feed;
+        let rfc3339 = |date: &NaiveDate| date.format("%Y-%m-%dT00:00:00Z").to_string();
+
+        fmt.write_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n")?;
+        fmt.write_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n")?;
+        writeln!(fmt, "  <title>{}</title>", escape(feed.title))?;
+        writeln!(fmt, "  <id>{}</id>", escape(feed.link))?;
+        writeln!(fmt, "  <link href=\"{}\"/>", escape(feed.link))?;
+        writeln!(fmt, "  <updated>{}</updated>", rfc3339(self.updated))?;
+        writeln!(fmt, "  <author><name>{}</name></author>", escape(feed.author))?;
+        if let Some(description) = feed.description {
+            writeln!(fmt, "  <subtitle>{}</subtitle>", escape(description))?;
+        }
+
+        for item in &feed.items {
+            fmt.write_str("  <entry>\n")?;
+            writeln!(fmt, "    <title>{}</title>", escape(&item.title))?;
+            writeln!(fmt, "    <id>{}</id>", escape(&item.id))?;
+            if let Some(link) = item.release.link() {
+                writeln!(fmt, "    <link href=\"{}\"/>", escape(link))?;
+            }
+            writeln!(fmt, "    <published>{}</published>", rfc3339(item.date))?;
+            writeln!(fmt, "    <updated>{}</updated>", rfc3339(item.date))?;
+            writeln!(
+                fmt,
+                "    <content type=\"html\">{}</content>",
+                escape(&item.content)
+            )?;
+            fmt.write_str("  </entry>\n")?;
+        }
+
+        fmt.write_str("</feed>\n")
+    }
+}
+
+/// Renders the releases of a changelog as an RSS 2.0 feed.
+pub(crate) struct Rss<'a>(Feed<'a>);
+
+impl<'a> Rss<'a> {
+    /// Checks that the changelog can be rendered with the given options.
+    pub(crate) fn new(changelog: &'a Changelog, options: &'a RenderOptions) -> Result<Self, FeedError> {
+        Ok(Self(Feed::new(changelog, options)?))
+    }
+}
+
+impl<'a> fmt::Display for Rss<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let feed = &self.0;
+        let rfc822 = |date: &NaiveDate| date.format("%a, %d %b %Y 00:00:00 +0000").to_string();
+
+        fmt.write_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n")?;
+        fmt.write_str("<rss version=\"2.0\">\n")?;
+        fmt.write_str("  <channel>\n")?;
+        writeln!(fmt, "    <title>{}</title>", escape(feed.title))?;
+        writeln!(fmt, "    <link>{}</link>", escape(feed.link))?;
+        let description = feed.description.unwrap_or(feed.title);
+        writeln!(fmt, "    <description>{}</description>", escape(description))?;
+        if let Some(date) = feed.items.iter().map(|item| item.date).max() {
+            writeln!(fmt, "    <lastBuildDate>{}</lastBuildDate>", rfc822(date))?;
+        }
+
+        for item in &feed.items {
+            fmt.write_str("    <item>\n")?;
+            writeln!(fmt, "      <title>{}</title>", escape(&item.title))?;
+            if let Some(link) = item.release.link() {
+                writeln!(fmt, "      <link>{}</link>", escape(link))?;
+            }
+            writeln!(
+                fmt,
+                "      <guid isPermaLink=\"{}\">{}</guid>",
+                item.release.link().is_some(),
+                escape(&item.id)
+            )?;
+            writeln!(fmt, "      <pubDate>{}</pubDate>", rfc822(item.date))?;
+            writeln!(
+                fmt,
+                "      <description>{}</description>",
+                escape(&item.content)
+            )?;
+            fmt.write_str("    </item>\n")?;
+        }
+
+        fmt.write_str("  </channel>\n")?;
+        fmt.write_str("</rss>\n")
+    }
+}
@@ -0,0 +1,657 @@
+use crate::changelog::{Change, Changelog, ChangelogBuilder, Release, ReleaseBuilder};
+use crate::config::Config;
+use crate::spec::SpecVersion;
+use crate::{ChangelogParserError, DEFAULT_DATE_FORMATS};
+use anyhow::Result;
+use chrono::NaiveDate;
+use derive_getters::Getters;
+use err_derive::Error;
+use std::collections::HashMap;
+use std::str::FromStr;
+use versions::Version;
+
+/// The title of an imported changelog whose source has none.
+const TITLE: &str = "Changelog";
+
+/// The change types the headings and tags of other conventions are imported
+/// as by default, matched regardless of case.
+const DEFAULT_HEADINGS: &[(&str, &str)] = &[
+    ("added", "Added"),
+    ("add", "Added"),
+    ("features", "Added"),
+    ("feature", "Added"),
+    ("feat", "Added"),
+    ("new features", "Added"),
+    ("enhancements", "Added"),
+    ("changed", "Changed"),
+    ("change", "Changed"),
+    ("changes", "Changed"),
+    ("what's changed", "Changed"),
+    ("performance improvements", "Changed"),
+    ("perf", "Changed"),
+    ("code refactoring", "Changed"),
+    ("deprecated", "Deprecated"),
+    ("deprecations", "Deprecated"),
+    ("removed", "Removed"),
+    ("breaking changes", "Removed"),
+    ("breaking", "Removed"),
+    ("removals", "Removed"),
+    ("reverts", "Removed"),
+    ("fixed", "Fixed"),
+    ("fix", "Fixed"),
+    ("fixes", "Fixed"),
+    ("bug fixes", "Fixed"),
+    ("security", "Security"),
+    ("security fixes", "Security"),
+];
+
+/// Headings whose entries are not changes, skipped without a warning.
+const IGNORED_HEADINGS: &[&str] = &["new contributors"];
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error(display = "unknown import format: {}", _0)]
+    UnknownFormat(String),
+    #[error(display = "invalid heading mapping, expected HEADING=TYPE: {}", _0)]
+    InvalidMapping(String),
+}
+
+/// A changelog convention that can be imported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    /// Release notes generated by GitHub, with a `What's Changed` section of
+    /// `* text by @user in #123` entries.
+    Github,
+    /// The output of conventional-changelog, with `### Features` and
+    /// `### Bug Fixes` sections.
+    Conventional,
+    /// Lists of entries tagged with their type, e.g. `- [fixed] ...`.
+    Tagged,
+}
+
+impl FromStr for ImportFormat {
+    type Err = ImportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "github" => Ok(ImportFormat::Github),
+            "conventional" => Ok(ImportFormat::Conventional),
+            "tagged" => Ok(ImportFormat::Tagged),
+            _ => Err(ImportError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+/// An imported changelog, along with the headings and tags whose entries were
+/// skipped because they map to no change type.
+#[derive(Debug, Clone, Getters)]
+pub struct Imported {
+    changelog: Changelog<'static>,
+    unmapped: Vec<String>,
+}
+
+impl Imported {
+    pub fn into_changelog(self) -> Changelog<'static> {
+        self.changelog
+    }
+}
+
+/// Converts changelogs written in other conventions into Keep a Changelog
+/// changelogs.
+///
+/// A heading is the heading of a release if it starts with a version of at
+/// least a major and minor number, e.g. `# [1.2.0](https://...) (2020-01-01)`
+/// or `## v1.2.0`, and the heading of a section of changes otherwise.
+pub struct Importer {
+    format: ImportFormat,
+    headings: HashMap<String, String>,
+}
+
+impl Importer {
+    pub fn new(format: ImportFormat) -> Self {
+        let headings = DEFAULT_HEADINGS
+            .iter()
+            .map(|(heading, kind)| (heading.to_string(), kind.to_string()))
+            .collect();
+
+        Self { format, headings }
+    }
+
+    /// Creates an importer using the heading mapping of the `[import]` section
+    /// of a project configuration along with the default one.
+    pub fn from_config(format: ImportFormat, config: &Config) -> Self {
+        let mut importer = Self::new(format);
+
+        if let Some(headings) = config
+            .import()
+            .as_ref()
+            .and_then(|import| import.headings().as_ref())
+        {
+            importer.set_headings(headings.clone());
+        }
+
+        importer
+    }
+
+    /// Maps section headings, or the tags of tagged entries, to the change
+    /// types their entries are imported as, in addition to the default
+    /// mapping.
+    pub fn set_headings(&mut self, headings: HashMap<String, String>) -> &mut Self {
+        for (heading, kind) in headings {
+            self.headings.insert(normalize_heading(&heading), kind);
+        }
+        self
+    }
+
+    /// The change type of the entries under a heading, or with a tag.
+    fn kind(&self, heading: &str) -> Option<&str> {
+        self.headings
+            .get(&normalize_heading(heading))
+            .map(String::as_str)
+    }
+
+    pub fn import(&self, text: &str) -> Result<Imported> {
+        let mut title = None;
+        let mut releases: Vec<PendingRelease> = Vec::new();
+        let mut section: Option<String> = None;
+        let mut unmapped: Vec<String> = Vec::new();
+        let mut entry: Option<String> = None;
+
+        for line in text.lines().map(str::trim_end) {
+            let trimmed = line.trim_start();
+
+            // Indented lines continue the entry before them, and blank ones
+            // separate its paragraphs.
+            if let Some(description) = entry.as_mut() {
+                if let Some(continued) = line.strip_prefix("  ") {
+                    description.push('\n');
+                    description.push_str(continued);
+                    continue;
+                } else if line.is_empty() && !description.ends_with('\n') {
+                    description.push('\n');
+                    continue;
+                }
+            }
+
+            if let Some(description) = entry.take() {
+                self.push_entry(
+                    &mut releases,
+                    &mut unmapped,
+                    section.as_deref(),
+                    &description,
+                )?;
+            }
+
+            if let Some((level, heading)) = heading(trimmed) {
+                if let Some(release) = PendingRelease::from_heading(heading) {
+                    releases.push(release);
+                    section = None;
+                } else if level == 1 && title.is_none() && releases.is_empty() {
+                    title = Some(heading.to_string());
+                } else {
+                    section = Some(heading.to_string());
+                }
+            } else if let Some(link) = full_changelog_link(trimmed) {
+                // GitHub release notes end with a link comparing the release to
+                // the one before it, which also names the version.
+                if releases.is_empty() {
+                    releases.push(PendingRelease::default());
+                }
+
+                let release = releases.last_mut().unwrap();
+                let version = match link.rsplit_once("...") {
+                    Some((_, version)) => version,
+                    None => link.rsplit('/').next().unwrap_or_default(),
+                };
+                if release.version.is_none() {
+                    release.version = parse_version(version);
+                }
+                release.link = Some(link.to_string());
+            } else if let Some(item) = list_item(trimmed) {
+                entry = Some(item.to_string());
+            } else if !trimmed.is_empty() && !trimmed.starts_with('<') && section.is_some() {
+                // Notes written as paragraphs, e.g. under `BREAKING CHANGES`.
+                entry = Some(trimmed.to_string());
+            }
+        }
+
+        if let Some(description) = entry {
+            self.push_entry(
+                &mut releases,
+                &mut unmapped,
+                section.as_deref(),
+                &description,
+            )?;
+        }
+
+        let releases = releases
+            .into_iter()
+            .map(PendingRelease::build)
+            .collect::<Result<Vec<Release>, _>>()?;
+
+        let changelog = ChangelogBuilder::default()
+            .title(title.unwrap_or_else(|| TITLE.to_string()))
+            .description(SpecVersion::LATEST.description())
+            .releases(releases)
+            .build()
+            .map_err(ChangelogParserError::ErrorBuildingRelease)?;
+
+        Ok(Imported {
+            changelog,
+            unmapped,
+        })
+    }
+
+    /// Adds an entry to the changes of the current release, or the heading it
+    /// is under to the unmapped ones.
+    fn push_entry(
+        &self,
+        releases: &mut Vec<PendingRelease>,
+        unmapped: &mut Vec<String>,
+        section: Option<&str>,
+        entry: &str,
+    ) -> Result<()> {
+        let (heading, change) = self.change(section, entry.trim_end());
+
+        match (heading, change) {
+            (_, Some((kind, description))) => {
+                // Entries before the first release heading are unreleased.
+                if releases.is_empty() {
+                    releases.push(PendingRelease::default());
+                }
+                releases
+                    .last_mut()
+                    .unwrap()
+                    .changes
+                    .push(Change::new(kind, description)?);
+            }
+            (Some(heading), None) if !unmapped.contains(&heading) => unmapped.push(heading),
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// The change type and description of an entry under a section heading,
+    /// along with the heading or tag it is mapped from. Entries that map to
+    /// no change type, or that are not changes, have none.
+    fn change<'a>(
+        &'a self,
+        section: Option<&'a str>,
+        entry: &'a str,
+    ) -> (Option<String>, Option<(&'a str, String)>) {
+        let (heading, description) = match (self.format, section) {
+            (ImportFormat::Tagged, _) => match tagged_entry(entry) {
+                Some((tag, description)) => (tag, description.to_string()),
+                None => ("untagged", entry.to_string()),
+            },
+            (ImportFormat::Github, Some(section)) => (section, link_references(entry)),
+            (ImportFormat::Conventional, Some(section)) => (section, entry.to_string()),
+            (_, None) => return (None, None),
+        };
+
+        if IGNORED_HEADINGS.contains(&normalize_heading(heading).as_str()) {
+            return (None, None);
+        }
+
+        match self.kind(heading) {
+            Some(kind) => (Some(heading.to_string()), Some((kind, description))),
+            None => (Some(heading.to_string()), None),
+        }
+    }
+}
+
+/// The level and text of an ATX heading, e.g. `## Features`. Lines such as
+/// `#123 fix crash` are not headings.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.bytes().take_while(|b| *b == b'#').count();
+    let text = &line[level..];
+
+    if (1..=6).contains(&level) && (text.is_empty() || text.starts_with(char::is_whitespace)) {
+        Some((level, text.trim()))
+    } else {
+        None
+    }
+}
+
+/// A release being imported.
+#[derive(Default)]
+struct PendingRelease {
+    version: Option<Version>,
+    link: Option<String>,
+    date: Option<NaiveDate>,
+    changes: Vec<Change<'static>>,
+}
+
+impl PendingRelease {
+    /// Parses a release heading, e.g. `[1.2.0](https://...) (2020-01-01)`,
+    /// `1.2.0 - 2020-01-01` or `v1.2.0`.
+    fn from_heading(heading: &str) -> Option<Self> {
+        let (version, mut rest) = if let Some(bracketed) = heading.strip_prefix('[') {
+            bracketed.split_once(']')?
+        } else {
+            let end = heading.find(char::is_whitespace).unwrap_or(heading.len());
+            heading.split_at(end)
+        };
+
+        let mut release = Self {
+            version: Some(parse_version(version)?),
+            ..Self::default()
+        };
+
+        if let Some(linked) = rest.strip_prefix('(') {
+            let (link, remaining) = linked.split_once(')')?;
+            release.link = Some(link.trim().to_string());
+            rest = remaining;
+        }
+
+        let rest = rest.trim();
+        let date = match rest.strip_prefix('(') {
+            Some(parenthesized) => parenthesized.split_once(')').map(|(date, _)| date),
+            None => rest.strip_prefix(|c| matches!(c, '-' | '/' | '\u{2013}' | '\u{2014}')),
+        };
+        release.date = date.and_then(parse_date);
+
+        Some(release)
+    }
+
+    fn build(self) -> Result<Release<'static>, ChangelogParserError> {
+        let mut release = ReleaseBuilder::default();
+        release.changes(self.changes);
+
+        if let Some(version) = self.version {
+            release.version(version);
+        }
+
+        if let Some(link) = self.link {
+            release.link(link);
+        }
+
+        if let Some(date) = self.date {
+            release.date(date);
+        }
+
+        release
+            .build()
+            .map_err(ChangelogParserError::ErrorBuildingRelease)
+    }
+}
+
+/// Lowercases a heading and strips the emoji and punctuation around it, e.g.
+/// `⚠ BREAKING CHANGES` to `breaking changes`.
+fn normalize_heading(heading: &str) -> String {
+    heading
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .replace('\u{2019}', "'")
+        .to_lowercase()
+}
+
+/// The text of a list item, without its marker.
+fn list_item(line: &str) -> Option<&str> {
+    line.strip_prefix("* ")
+        .or_else(|| line.strip_prefix("- "))
+        .or_else(|| line.strip_prefix("+ "))
+        .map(str::trim_start)
+}
+
+/// Splits the tag off a tagged entry, e.g. `[fixed] text`.
+fn tagged_entry(entry: &str) -> Option<(&str, &str)> {
+    let (tag, description) = entry.strip_prefix('[')?.split_once(']')?;
+
+    Some((tag.trim(), description.trim_start()))
+}
+
+/// The link of a `**Full Changelog**: https://...` line.
+fn full_changelog_link(line: &str) -> Option<&str> {
+    let link = line.strip_prefix("**Full Changelog**:")?.trim();
+
+    if link.starts_with("http") {
+        Some(link)
+    } else {
+        None
+    }
+}
+
+/// Parses a version, which may have a `v` prefix. Unlike in Keep a Changelog
+/// changelogs, any heading may be a release heading, so versions must start
+/// with a major and minor number, e.g. `1.2`, for headings such as `3rd-party`
+/// not to be taken as releases.
+fn parse_version(text: &str) -> Option<Version> {
+    let text = text.trim();
+    let text = text.strip_prefix(['v', 'V']).unwrap_or(text);
+
+    let major = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let minor = text[major..]
+        .strip_prefix('.')
+        .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+
+    if major > 0 && minor {
+        Version::new(text)
+    } else {
+        None
+    }
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    DEFAULT_DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text.trim(), format).ok())
+}
+
+/// Shortens the links to GitHub pull requests and issues in an entry, e.g.
+/// `https://github.com/o/r/pull/123` to `[#123](https://github.com/o/r/pull/123)`.
+fn link_references(entry: &str) -> String {
+    entry
+        .split(' ')
+        .map(|word| {
+            let url = word.trim_end_matches(['.', ',', ';']);
+            let number = url
+                .strip_prefix("https://github.com/")
+                .and_then(|path| {
+                    let mut parts = path.split('/').skip(2);
+                    match (parts.next(), parts.next(), parts.next()) {
+                        (Some("pull"), Some(number), None)
+                        | (Some("issues"), Some(number), None) => Some(number),
+                        _ => None,
+                    }
+                })
+                .filter(|number| number.chars().all(|c| c.is_ascii_digit()));
+
+            match number {
+                Some(number) => format!("[#{}]({}){}", number, url, &word[url.len()..]),
+                None => word.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import(format: ImportFormat, text: &str) -> Imported {
+        Importer::new(format).import(text).unwrap()
+    }
+
+    fn version(release: &Release) -> Option<String> {
+        release.version().as_ref().map(Version::to_string)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    #[test]
+    fn imports_github_release_notes() {
+        let imported = import(
+            ImportFormat::Github,
+            "## What's Changed\n\
+             ### 🎉 New Features\n\
+             * Add retries by @alice in https://github.com/acme/widget/pull/123\n\
+             ### Bug Fixes\n\
+             * Fix a crash by @carol in https://github.com/acme/widget/pull/125.\n\
+             ### Other\n\
+             * Bump deps by @dependabot in https://github.com/acme/widget/pull/127\n\
+             \n\
+             ## New Contributors\n\
+             * @carol made their first contribution in https://github.com/acme/widget/pull/125\n\
+             \n\
+             **Full Changelog**: https://github.com/acme/widget/compare/v1.1.0...v1.2.0\n",
+        );
+
+        let releases = imported.changelog().releases();
+        assert_eq!(releases.len(), 1);
+        assert_eq!(version(&releases[0]), Some("1.2.0".into()));
+        assert_eq!(
+            releases[0].link().as_deref(),
+            Some("https://github.com/acme/widget/compare/v1.1.0...v1.2.0")
+        );
+        assert_eq!(
+            releases[0].changes(),
+            &vec![
+                Change::Added(
+                    "Add retries by @alice in \
+                     [#123](https://github.com/acme/widget/pull/123)"
+                        .into()
+                ),
+                Change::Fixed(
+                    "Fix a crash by @carol in \
+                     [#125](https://github.com/acme/widget/pull/125)."
+                        .into()
+                ),
+            ]
+        );
+        assert_eq!(imported.unmapped(), &vec!["Other".to_string()]);
+    }
+
+    #[test]
+    fn imports_conventional_changelogs() {
+        let imported = import(
+            ImportFormat::Conventional,
+            "# Changelog\n\
+             \n\
+             ## [1.2.0](https://github.com/acme/widget/compare/v1.1.0...v1.2.0) (2021-03-04)\n\
+             \n\
+             ### ⚠ BREAKING CHANGES\n\
+             \n\
+             * **api:** the `timeout` option is now in milliseconds\n\
+             \n\
+             ### Features\n\
+             \n\
+             * support proxies, which needs a long description that continues on the\n  \
+             next line\n\
+             \n\
+             ### Bug Fixes\n\
+             \n\
+             * crash on empty config\n\
+             \n\
+             #128 fix the exit code\n\
+             \n\
+             <a name=\"1.0.0\"></a>\n\
+             # 1.0.0 (2021-01-01)\n\
+             \n\
+             ### Features\n\
+             \n\
+             * initial release\n",
+        );
+
+        let changelog = imported.changelog();
+        assert_eq!(changelog.title(), "Changelog");
+
+        let releases = changelog.releases();
+        assert_eq!(releases.len(), 2);
+        assert_eq!(version(&releases[0]), Some("1.2.0".into()));
+        assert_eq!(releases[0].date(), &date(2021, 3, 4));
+        assert_eq!(
+            releases[0].changes(),
+            &vec![
+                Change::Removed("**api:** the `timeout` option is now in milliseconds".into()),
+                Change::Added(
+                    "support proxies, which needs a long description that continues on the\n\
+                     next line"
+                        .into()
+                ),
+                Change::Fixed("crash on empty config".into()),
+                Change::Fixed("#128 fix the exit code".into()),
+            ]
+        );
+        assert_eq!(version(&releases[1]), Some("1.0.0".into()));
+        assert_eq!(releases[1].date(), &date(2021, 1, 1));
+        assert_eq!(releases[1].changes(), &vec![Change::Added("initial release".into())]);
+        assert!(imported.unmapped().is_empty());
+    }
+
+    #[test]
+    fn imports_tagged_entries() {
+        let imported = import(
+            ImportFormat::Tagged,
+            "# History\n\
+             \n\
+             ## 2.0.0 - 2022-05-01\n\
+             - [added] Dark mode\n\
+             - [wip] Something half done\n\
+             - untagged thing\n\
+             \n\
+             ## 1.0.0 (2022-01-01)\n\
+             - [feature] First release\n  \
+             with two lines\n\
+             \n  \
+             and a second paragraph\n\
+             - [security] Patch XSS\n",
+        );
+
+        let changelog = imported.changelog();
+        assert_eq!(changelog.title(), "History");
+
+        let releases = changelog.releases();
+        assert_eq!(releases.len(), 2);
+        assert_eq!(version(&releases[0]), Some("2.0.0".into()));
+        assert_eq!(releases[0].date(), &date(2022, 5, 1));
+        assert_eq!(releases[0].changes(), &vec![Change::Added("Dark mode".into())]);
+        assert_eq!(version(&releases[1]), Some("1.0.0".into()));
+        assert_eq!(releases[1].date(), &date(2022, 1, 1));
+        assert_eq!(
+            releases[1].changes(),
+            &vec![
+                Change::Added("First release\nwith two lines\n\nand a second paragraph".into()),
+                Change::Security("Patch XSS".into()),
+            ]
+        );
+        assert_eq!(
+            imported.unmapped(),
+            &vec!["wip".to_string(), "untagged".to_string()]
+        );
+    }
+
+    #[test]
+    fn requires_a_major_and_minor_version_in_release_headings() {
+        let imported = import(
+            ImportFormat::Conventional,
+            "## 1.0.0 (2021-01-01)\n\
+             \n\
+             ### 3rd-party\n\
+             \n\
+             * bump the http client\n\
+             \n\
+             ### 2 Features\n\
+             \n\
+             * two features\n",
+        );
+
+        let releases = imported.changelog().releases();
+        assert_eq!(releases.len(), 1);
+        assert_eq!(version(&releases[0]), Some("1.0.0".into()));
+        assert!(releases[0].changes().is_empty());
+        assert_eq!(
+            imported.unmapped(),
+            &vec!["3rd-party".to_string(), "2 Features".to_string()]
+        );
+
+        assert!(parse_version("v1.2").is_some());
+        assert!(parse_version("10.0.0-rc.1").is_some());
+        assert!(parse_version("3rd-party").is_none());
+        assert!(parse_version("2").is_none());
+        assert!(parse_version("1.x").is_none());
+    }
+}
@@ -0,0 +1,150 @@
+use crate::changelog::{Changelog, ChangelogBuilder, ReleaseBuilder};
+use crate::spec::SpecVersion;
+use anyhow::Result;
+use chrono::NaiveDate;
+use derive_getters::Getters;
+use err_derive::Error;
+use std::path::Path;
+use std::process::Command;
+use versions::Version;
+
+#[derive(Debug, Error)]
+pub enum InitError {
+    #[error(display = "{} already exists, use --force to overwrite it", _0)]
+    FileExists(String),
+    #[error(display = "unable to read the tags of the git repository: {}", _0)]
+    GitTags(String),
+}
+
+/// A git tag naming a version, e.g. `v1.2.0`.
+#[derive(Debug, Clone, Getters, PartialEq)]
+pub struct Tag {
+    name: String,
+    version: Version,
+    /// The date the tag was created, or of the commit it points to.
+    date: NaiveDate,
+}
+
+impl Tag {
+    pub fn new(name: String, version: Version, date: NaiveDate) -> Self {
+        Self {
+            name,
+            version,
+            date,
+        }
+    }
+}
+
+/// Creates a changelog with the standard description of the latest version of
+/// Keep a Changelog, an empty unreleased section, and a release without
+/// changes for each tag, which should be sorted newest first.
+///
+/// With a repository URL, each release links to the changes since the tag
+/// before it, and the unreleased section to the changes since the latest tag.
+pub fn changelog(
+    title: &str,
+    repo_url: Option<&str>,
+    tags: &[Tag],
+) -> Result<Changelog<'static>> {
+    let mut unreleased = ReleaseBuilder::default();
+    if let Some(url) = repo_url {
+        match tags.first() {
+            Some(latest) => unreleased.link(format!("{}/compare/{}...HEAD", url, latest.name)),
+            None => unreleased.link(format!("{}/commits/HEAD", url)),
+        };
+    }
+
+    let mut releases = vec![unreleased.build().map_err(anyhow::Error::msg)?];
+    for (index, tag) in tags.iter().enumerate() {
+        let mut release = ReleaseBuilder::default();
+        release.version(tag.version.clone()).date(tag.date);
+
+        if let Some(url) = repo_url {
+            match tags.get(index + 1) {
+                Some(previous) => {
+                    release.link(format!("{}/compare/{}...{}", url, previous.name, tag.name))
+                }
+                None => release.link(format!("{}/releases/tag/{}", url, tag.name)),
+            };
+        }
+
+        releases.push(release.build().map_err(anyhow::Error::msg)?);
+    }
+
+    let changelog = ChangelogBuilder::default()
+        .title(title.to_string())
+        .description(SpecVersion::LATEST.description())
+        .releases(releases)
+        .build()
+        .map_err(anyhow::Error::msg)?;
+
+    Ok(changelog)
+}
+
+/// The URL of the origin remote of the git repository in `directory`, as an
+/// https URL, e.g. `https://github.com/example/example` for
+/// `git@github.com:example/example.git` or
+/// `ssh://git@github.com:22/example/example.git`.
+pub fn origin_url(directory: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .current_dir(directory)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let url = String::from_utf8(output.stdout).ok()?;
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+
+    if let Some(address) = url.strip_prefix("ssh://") {
+        // The user and port of the ssh address are not part of the https URL.
+        let (host, path) = address.split_once('/')?;
+        let host = host.rsplit('@').next().unwrap_or(host);
+        let host = host.split(':').next().unwrap_or(host);
+
+        return Some(format!("https://{}/{}", host, path));
+    }
+
+    match url.strip_prefix("git@") {
+        Some(path) => Some(format!("https://{}", path.replacen(':', "/", 1))),
+        None if url.starts_with("https://") || url.starts_with("http://") => Some(url.into()),
+        None => None,
+    }
+}
+
+/// The tags of the git repository in `directory` that name a version, e.g.
+/// `1.2.0` or `v1.2.0`, newest version first.
+pub fn version_tags(directory: &Path) -> Result<Vec<Tag>> {
+    let output = Command::new("git")
+        .args([
+            "for-each-ref",
+            "--format=%(refname:short) %(creatordate:short)",
+            "refs/tags",
+        ])
+        .current_dir(directory)
+        .output()?;
+
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(InitError::GitTags(message).into());
+    }
+
+    let mut tags: Vec<Tag> = String::from_utf8(output.stdout)?
+        .lines()
+        .filter_map(|line| {
+            let (name, date) = line.split_once(' ')?;
+            let version = name.strip_prefix(['v', 'V']).unwrap_or(name);
+            if !version.starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+
+            let version = Version::new(version)?;
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+
+            Some(Tag::new(name.to_string(), version, date))
+        })
+        .collect();
+    tags.sort_by(|a, b| b.version.cmp(&a.version));
+
+    Ok(tags)
+}
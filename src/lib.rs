@@ -1,30 +1,86 @@
+// err_derive implements `Error` and `Display` inside a named constant, which
+// rustc reports as a non-local definition.
+#![allow(non_local_definitions)]
+
 use anyhow::Result;
-use changelog::{Change, Changelog, ChangelogBuilder, Release, ReleaseBuilder};
+use changelog::{Changelog, ChangelogBuilder, ReleaseBuilder};
 use chrono::NaiveDate;
 use err_derive::Error;
-use pulldown_cmark::{Event, LinkType, Parser, Tag};
+use config::Config;
+use locale::{Locale, EN};
+use markdown::MarkdownReleases;
+use serde_derive::Deserialize;
+use serde_path_to_error::Segment;
 use versions::Version;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::PathBuf;
+use std::str::{self, FromStr};
 
 pub mod changelog;
+pub mod config;
+pub mod conventional;
+pub mod debian;
+pub mod feed;
+pub mod import;
+pub mod init;
+pub mod locale;
+mod markdown;
+mod markup;
+pub mod render;
+pub mod rpm;
+pub mod spec;
+mod text;
+mod wrap;
+
+pub use markdown::Releases;
+
+/// A format a changelog can be parsed from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChangelogFormat {
+    Markdown,
+    Json,
+    Yaml,
+}
 
-#[derive(Clone, Debug)]
-enum ChangelogFormat {
+/// A format a changelog can be rendered in.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[serde(alias = "md")]
     Markdown,
     Json,
+    #[serde(alias = "yml")]
     Yaml,
+    /// A `debian/changelog` file.
+    Debian,
+    /// The `%changelog` section of an RPM spec file.
+    Rpm,
+    /// An Atom feed of releases.
+    Atom,
+    /// An RSS feed of releases.
+    Rss,
+    /// An AsciiDoc document.
+    #[serde(rename = "asciidoc", alias = "adoc")]
+    AsciiDoc,
+    /// A reStructuredText document.
+    Rst,
+    /// Plain text for reading in a terminal.
+    Text,
+    /// conventional-changelog Markdown.
+    Conventional,
 }
 
-#[derive(Clone, Debug)]
-enum ChangelogSection {
-    None,
-    Title,
-    Description,
-    ReleaseHeader,
-    ChangesetHeader,
-    Changeset(String),
+impl From<ChangelogFormat> for OutputFormat {
+    fn from(format: ChangelogFormat) -> Self {
+        match format {
+            ChangelogFormat::Markdown => OutputFormat::Markdown,
+            ChangelogFormat::Json => OutputFormat::Json,
+            ChangelogFormat::Yaml => OutputFormat::Yaml,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -33,151 +89,272 @@ pub enum ChangelogParserError {
     UnableToDetermineFormat,
     #[error(display = "error building release")]
     ErrorBuildingRelease(String),
+    #[error(display = "invalid changelog at {}: {}", pointer, message)]
+    InvalidDocument { pointer: String, message: String },
 }
 
+/// The date formats tried, in order, when parsing the date of a release
+/// heading.
+pub const DEFAULT_DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%B %d, %Y",
+    "%b %d, %Y",
+    "%d %B %Y",
+    "%d %b %Y",
+];
+
+/// The separators accepted between the version and date of a release heading
+/// in addition to the configured separator.
+pub const DEFAULT_HEADING_SEPARATORS: &[&str] = &["-", "\u{2013}", "\u{2014}"];
+
+/// Parses changelogs in any of the supported formats.
+///
+/// The default parser uses `-` as the heading separator.
 pub struct ChangelogParser {
     separator: String,
-    wrap: Option<usize>,
+    heading_separators: Vec<String>,
+    date_formats: Vec<String>,
+    section_aliases: HashMap<String, String>,
+    locale: &'static Locale,
+}
+
+impl Default for ChangelogParser {
+    fn default() -> Self {
+        Self::with_separator("-".into())
+    }
 }
 
 impl ChangelogParser {
-    pub fn new(separator: String, wrap: Option<usize>) -> Self {
+    #[deprecated(
+        since = "0.10.0",
+        note = "use `with_separator`. `wrap` has no effect, the wrap width is set with \
+                `RenderOptions::set_wrap` or the `wrap-at` configuration option"
+    )]
+    pub fn new(separator: String, _wrap: Option<usize>) -> Self {
+        Self::with_separator(separator)
+    }
+
+    pub fn with_separator(separator: String) -> Self {
+        let mut heading_separators = vec![separator.clone()];
+        heading_separators.extend(DEFAULT_HEADING_SEPARATORS.iter().map(|s| s.to_string()));
+        heading_separators.dedup();
+
         Self {
             separator,
-            wrap,
+            heading_separators,
+            date_formats: DEFAULT_DATE_FORMATS.iter().map(|f| f.to_string()).collect(),
+            section_aliases: HashMap::new(),
+            locale: &EN,
         }
     }
 
-    pub fn parse(&self, path: PathBuf) -> Result<Changelog> {
-        let mut document = String::new();
-        File::open(path.clone())?.read_to_string(&mut document)?;
-        self.parse_buffer(document)
-    }
+    /// Creates a parser from the options set in a project configuration, using
+    /// the defaults for any that are unset.
+    pub fn from_config(config: &Config) -> Self {
+        let separator = config.separator().clone().unwrap_or_else(|| "-".into());
+        let mut parser = Self::with_separator(separator);
 
-    pub fn parse_buffer(&self, buffer: String) -> Result<Changelog> {
-        match Self::get_format_from_buffer(buffer.clone()) {
-            Ok(format) => match format {
-                ChangelogFormat::Markdown => self.parse_markdown(buffer),
-                ChangelogFormat::Json => Self::parse_json(buffer),
-                ChangelogFormat::Yaml => Self::parse_yaml(buffer),
-            },
-            _ => Err(ChangelogParserError::UnableToDetermineFormat.into()),
+        if let Some(heading_separators) = config.heading_separators() {
+            parser.set_heading_separators(heading_separators.clone());
         }
-    }
 
-    fn parse_markdown(&self, markdown: String) -> Result<Changelog> {
-        let parser = Parser::new(&markdown);
-
-        let mut section = ChangelogSection::None;
-
-        let mut title = String::new();
-        let mut description = String::new();
-        let mut description_links = String::new();
-        let mut releases: Vec<Release> = Vec::new();
-
-        let mut release = ReleaseBuilder::default();
-        let mut changeset: Vec<Change> = Vec::new();
-        let mut accumulator = String::new();
-        let mut link_accumulator = String::new();
-
-        for event in parser {
-            match event {
-                // Headings.
-                Event::Start(Tag::Header(1)) => section = ChangelogSection::Title,
-                Event::End(Tag::Header(1)) => section = ChangelogSection::Description,
-                Event::Start(Tag::Header(2)) => {
-                    match section {
-                        ChangelogSection::Description => {
-                            description = accumulator.clone();
-                            accumulator = String::new();
-                        }
-                        ChangelogSection::Changeset(_) | ChangelogSection::ReleaseHeader => {
-                            self.parse_release_header(&mut release, &mut accumulator);
-                            self.build_release(&mut releases, &mut release, &mut changeset)?;
-                        }
-                        _ => (),
-                    }
-
-                    section = ChangelogSection::ReleaseHeader;
-                }
-                Event::Start(Tag::Header(3)) => section = ChangelogSection::ChangesetHeader,
+        if let Some(date_formats) = config.date_formats() {
+            parser.set_date_formats(date_formats.clone());
+        }
 
-                // Links.
-                Event::Start(Tag::Link(LinkType::Inline, _, _)) => accumulator.push_str("["),
-                Event::Start(Tag::Link(LinkType::Collapsed, _, _)) => {
-                    accumulator.push_str("[");
-                    link_accumulator = String::from("[");
-                }
-                Event::End(Tag::Link(LinkType::Inline, href, _)) => {
-                    accumulator.push_str(&format!("]({})", href));
-                }
-                Event::End(Tag::Link(LinkType::Collapsed, href, _)) => {
-                    accumulator.push_str("][]");
-                    link_accumulator.push_str(&format!("]: {}\n", href));
-                    description_links.push_str(&link_accumulator);
-                    link_accumulator = String::new();
-                }
-                Event::Start(Tag::Link(LinkType::Shortcut, href, _)) => {
-                    release.link(href.to_string());
+        // Sections are parsed back from the names they are rendered with, as
+        // well as from any other configured aliases.
+        let mut section_aliases = HashMap::new();
+        if let Some(section_aliases_config) = config.section_aliases() {
+            for (kind, aliases) in section_aliases_config {
+                for alias in aliases {
+                    section_aliases.insert(alias.clone(), kind.clone());
                 }
+            }
+        }
+        if let Some(section_names) = config.section_names() {
+            for (kind, name) in section_names {
+                section_aliases.insert(name.clone(), kind.clone());
+            }
+        }
+        parser.set_section_aliases(section_aliases);
 
-                // Items.
-                Event::End(Tag::Item) => {
-                    if let ChangelogSection::Changeset(name) = section.clone() {
-                        changeset.push(Change::new(&name, accumulator)?);
+        if let Some(locale) = config.locale().as_deref().and_then(Locale::find) {
+            parser.set_locale(locale);
+        }
 
-                        accumulator = String::new();
-                    }
-                }
+        // Dates written in the output format can always be read back.
+        if let Some(date_format) = config.output_date_format() {
+            if !parser.date_formats.contains(date_format) {
+                parser.date_formats.push(date_format.clone());
+            }
+        }
+
+        parser
+    }
 
-                // Line breaks.
-                Event::SoftBreak => accumulator.push_str("\n"),
-                Event::End(Tag::Paragraph) => accumulator.push_str("\n\n"),
+    /// Sets the separators accepted between the version and date of a release
+    /// heading. The output separator is always accepted.
+    pub fn set_heading_separators(&mut self, separators: Vec<String>) -> &mut Self {
+        let mut heading_separators = vec![self.separator.clone()];
+        heading_separators.extend(
+            separators
+                .into_iter()
+                .filter(|separator| *separator != self.separator),
+        );
+        self.heading_separators = heading_separators;
+        self
+    }
 
-                // Inline code.
-                Event::Code(text) => accumulator.push_str(&format!("`{}`", text)),
+    /// Sets the `chrono` formats a release heading date is parsed with, tried in
+    /// order.
+    pub fn set_date_formats(&mut self, date_formats: Vec<String>) -> &mut Self {
+        self.date_formats = date_formats;
+        self
+    }
 
-                // Text formatting.
-                Event::Start(Tag::Strong) | Event::End(Tag::Strong) => accumulator.push_str("**"),
-                Event::Start(Tag::Emphasis) | Event::End(Tag::Emphasis) => {
-                    accumulator.push_str("_")
-                }
-                Event::Start(Tag::Strikethrough) | Event::End(Tag::Strikethrough) => {
-                    accumulator.push_str("~~")
-                }
+    /// Sets alternative names that change type headings are parsed from, such
+    /// as `Corrigé` for `Fixed`, mapping each alias to the change type.
+    /// Aliases are matched regardless of case.
+    pub fn set_section_aliases(&mut self, aliases: HashMap<String, String>) -> &mut Self {
+        self.section_aliases = aliases
+            .into_iter()
+            .map(|(alias, kind)| (alias.to_lowercase(), kind))
+            .collect();
+        self
+    }
 
-                // Text.
-                Event::Text(text) => match section {
-                    ChangelogSection::Title => title = text.to_string(),
-                    ChangelogSection::Description => {
-                        accumulator.push_str(&text);
-
-                        if !link_accumulator.is_empty() {
-                            link_accumulator.push_str(&text);
-                        }
-                    }
-                    ChangelogSection::ChangesetHeader => {
-                        self.parse_release_header(&mut release, &mut accumulator);
-
-                        section = ChangelogSection::Changeset(text.to_string())
-                    }
-                    ChangelogSection::Changeset(_) | ChangelogSection::ReleaseHeader => accumulator.push_str(&text),
-                    _ => (),
-                },
-                _ => (),
-            };
+    /// Sets the locale a changelog is written in. Its section names, words for
+    /// unreleased and yanked releases, and date format are accepted along with
+    /// the English ones.
+    pub fn set_locale(&mut self, locale: &'static Locale) -> &mut Self {
+        for (kind, name) in locale.section_names() {
+            self.section_aliases.entry(name.to_lowercase()).or_insert(kind);
+        }
+
+        let date_format = locale.date_format().to_string();
+        if !self.date_formats.contains(&date_format) {
+            self.date_formats.push(date_format);
+        }
+
+        self.locale = locale;
+        self
+    }
+
+    /// The change type a change type heading stands for.
+    fn section_kind<'a>(&'a self, heading: &'a str) -> &'a str {
+        self.section_aliases
+            .get(&heading.to_lowercase())
+            .map(String::as_str)
+            .unwrap_or(heading)
+    }
+
+    pub fn parse(&self, path: PathBuf) -> Result<Changelog<'static>> {
+        self.parse_reader(File::open(path)?)
+    }
+
+    /// Parses a changelog read from `reader`, such as an HTTP body or a file in
+    /// an archive.
+    pub fn parse_reader<R: Read>(&self, mut reader: R) -> Result<Changelog<'static>> {
+        let mut document = Vec::new();
+        reader.read_to_end(&mut document)?;
+        self.parse_bytes(&document).map(Changelog::into_owned)
+    }
+
+    /// Parses a UTF-8 encoded changelog, ignoring a leading byte order mark and
+    /// converting CRLF line endings.
+    pub fn parse_bytes<'a>(&self, bytes: &'a [u8]) -> Result<Changelog<'a>> {
+        let document = str::from_utf8(bytes)?;
+
+        if document.contains('\r') {
+            let document = document.replace("\r\n", "\n");
+            self.parse_str(&document).map(Changelog::into_owned)
+        } else {
+            self.parse_str(document)
         }
+    }
+
+    /// Determines the format of a changelog from the contents of `buffer`.
+    pub fn detect_format(buffer: &str) -> Result<ChangelogFormat> {
+        Self::get_format_from_buffer(strip_bom(buffer))
+    }
+
+    pub fn parse_buffer(&self, buffer: String) -> Result<Changelog<'static>> {
+        self.parse_str(&buffer).map(Changelog::into_owned)
+    }
+
+    /// Parses a changelog, ignoring a leading byte order mark. The changelog
+    /// borrows its text from `buffer` where it can.
+    pub fn parse_str<'a>(&self, buffer: &'a str) -> Result<Changelog<'a>> {
+        let buffer = strip_bom(buffer);
 
-        self.build_release(&mut releases, &mut release, &mut changeset)?;
+        match Self::get_format_from_buffer(buffer) {
+            Ok(format) => match format {
+                ChangelogFormat::Markdown => self.parse_markdown(buffer, None),
+                ChangelogFormat::Json => Self::parse_json(buffer),
+                ChangelogFormat::Yaml => Self::parse_yaml(buffer),
+            },
+            _ => Err(ChangelogParserError::UnableToDetermineFormat.into()),
+        }
+    }
 
-        if !description_links.is_empty() {
-            description = format!("{}{}\n", description, description_links);
+    /// Parses a changelog, keeping only its first `count` releases.
+    ///
+    /// Releases of a markdown changelog after the first `count` are not parsed
+    /// at all.
+    pub fn parse_head<'a>(&self, buffer: &'a str, count: usize) -> Result<Changelog<'a>> {
+        let buffer = strip_bom(buffer);
+
+        match Self::get_format_from_buffer(buffer)? {
+            ChangelogFormat::Markdown => match markdown::truncate(buffer, count) {
+                Cow::Borrowed(markdown) => self.parse_markdown(markdown, Some(count)),
+                Cow::Owned(markdown) => self
+                    .parse_markdown(&markdown, Some(count))
+                    .map(Changelog::into_owned),
+            },
+            ChangelogFormat::Json => Self::parse_json(buffer),
+            ChangelogFormat::Yaml => Self::parse_yaml(buffer),
         }
+        .map(|mut changelog| {
+            changelog.releases_mut().truncate(count);
+            changelog
+        })
+    }
+
+    /// Returns an iterator over the releases of a changelog.
+    ///
+    /// Releases of a markdown changelog are parsed one at a time as the
+    /// iterator is advanced. JSON and YAML changelogs are parsed up front.
+    pub fn releases<'a>(&'a self, buffer: &'a str) -> Result<Releases<'a>> {
+        let buffer = strip_bom(buffer);
+        let releases = match Self::get_format_from_buffer(buffer)? {
+            ChangelogFormat::Markdown => {
+                let mut releases = MarkdownReleases::new(self, buffer);
+                releases.read_head();
+
+                Releases::markdown(releases)
+            }
+            ChangelogFormat::Json => Releases::parsed(Self::parse_json(buffer)?.into_releases()),
+            ChangelogFormat::Yaml => Releases::parsed(Self::parse_yaml(buffer)?.into_releases()),
+        };
+
+        Ok(releases)
+    }
+
+    fn parse_markdown<'a>(&self, markdown: &'a str, count: Option<usize>) -> Result<Changelog<'a>> {
+        let mut parsed = MarkdownReleases::new(self, markdown);
+        let (title, description) = parsed.read_head();
+        let releases = parsed
+            .by_ref()
+            .take(count.unwrap_or(usize::MAX))
+            .collect::<Result<Vec<_>>>()?;
 
         let changelog = ChangelogBuilder::default()
             .title(title)
             .description(description)
             .releases(releases)
+            .footnotes(parsed.into_footnotes())
             .build()
             .map_err(ChangelogParserError::ErrorBuildingRelease)?;
 
@@ -185,54 +362,138 @@ impl ChangelogParser {
     }
 
     fn parse_release_header(&self, release: &mut ReleaseBuilder, accumulator: &mut String) {
-        let delimiter = format!(" {} ", self.separator);
-        if let Some((left, right)) = accumulator.trim().split_once(&delimiter) {
-            if right.contains("YANKED") {
-                release.yanked(true);
+        let heading = std::mem::take(accumulator);
+        let heading = heading.trim();
+
+        // The version is either bracketed, or runs until the first whitespace or
+        // opening parenthesis.
+        let (version, rest) = if let Some(bracketed) = heading.strip_prefix('[') {
+            match bracketed.split_once(']') {
+                Some((version, rest)) => (version, rest),
+                None => (bracketed, ""),
             }
+        } else if let Some(end) = self.unreleased_prefix(heading) {
+            heading.split_at(end)
+        } else {
+            let end = heading
+                .find(|c: char| c.is_whitespace() || c == '(')
+                .unwrap_or(heading.len());
+            heading.split_at(end)
+        };
 
-            let right = &right.replace(" [YANKED]", "");
-            if let Ok(date) = NaiveDate::parse_from_str(&right, "%Y-%m-%d") {
-                release.date(date);
-            }
+        // Bracketed annotations such as `[YANKED]` or `[LTS]` trail the heading.
+        let mut rest = rest.trim();
+        let mut labels = Vec::new();
+        while let Some(stripped) = rest.strip_suffix(']') {
+            let (remaining, label) = match stripped.rsplit_once('[') {
+                Some(split) => split,
+                None => break,
+            };
 
-            if let Some(version) = Version::new(&left) {
-                release.version(version);
+            if self.locale.is_yanked(label) {
+                release.yanked(true);
+            } else if !label.trim().is_empty() {
+                labels.insert(0, label.trim().to_string().into());
             }
+
+            rest = remaining.trim_end();
+        }
+        release.labels(labels);
+
+        let version = version.trim();
+        if version.is_empty() || self.locale.is_unreleased(version) {
+            return;
         }
 
-        *accumulator = String::new();
+        match Version::new(version) {
+            Some(parsed) => release.version(parsed),
+            None => release.invalid_version(version.to_string()),
+        };
+
+        if let Some(date) = self.parse_release_date(rest) {
+            release.date(date);
+        }
     }
 
-    fn build_release(&self, releases: &mut Vec<Release>, release: &mut ReleaseBuilder, changeset: &mut Vec<Change>) -> Result<()> {
-        release.changes(changeset.clone());
-        release.separator(self.separator.clone());
-        release.wrap(self.wrap);
-        releases.push(
-            release
-                .build()
-                .map_err(ChangelogParserError::ErrorBuildingRelease)?
-        );
+    fn parse_release_date(&self, text: &str) -> Option<NaiveDate> {
+        let text = if let Some(parenthesized) = text.strip_prefix('(') {
+            parenthesized.strip_suffix(')')?
+        } else {
+            self.heading_separators
+                .iter()
+                .filter(|separator| !separator.is_empty())
+                .find_map(|separator| text.strip_prefix(separator.as_str()))?
+        };
+
+        self.date_formats
+            .iter()
+            .find_map(|format| self.locale.parse_date(text.trim(), format))
+    }
 
-        *changeset = Vec::new();
-        *release = ReleaseBuilder::default();
+    /// The length of the word for an unreleased release at the start of an
+    /// unbracketed heading, which may contain spaces, e.g. `Non publié`.
+    fn unreleased_prefix(&self, heading: &str) -> Option<usize> {
+        let unreleased = self.locale.unreleased();
+        let prefix = heading.get(..unreleased.len())?;
 
-        Ok(())
+        if self.locale.is_unreleased(prefix) {
+            Some(unreleased.len())
+        } else {
+            None
+        }
     }
 
-    fn parse_json(json: String) -> Result<Changelog> {
-        let changelog: Changelog = serde_json::from_str(&json)?;
+    fn parse_json(json: &str) -> Result<Changelog<'_>> {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let changelog: Changelog = serde_path_to_error::deserialize(&mut deserializer)
+            .map_err(|error| Self::invalid_document(error.path(), error.inner().to_string()))?;
+        deserializer.end()?;
 
         Ok(changelog)
     }
 
-    fn parse_yaml(yaml: String) -> Result<Changelog> {
-        let changelog: Changelog = serde_yaml::from_str(&yaml)?;
+    fn parse_yaml(yaml: &str) -> Result<Changelog<'_>> {
+        let deserializer = serde_yaml::Deserializer::from_str(yaml);
+        let changelog: Changelog =
+            serde_path_to_error::deserialize(deserializer).map_err(|error| {
+                // serde_yaml starts its messages with its own path to the value,
+                // e.g. `releases[0].date: `, which the pointer replaces.
+                let message = error.inner().to_string();
+                let message = match message.split_once(": ") {
+                    Some((path, rest)) if !path.contains(char::is_whitespace) => rest.into(),
+                    _ => message,
+                };
+
+                Self::invalid_document(error.path(), message)
+            })?;
 
         Ok(changelog)
     }
 
-    fn get_format_from_buffer(buffer: String) -> Result<ChangelogFormat> {
+    /// Converts a deserialization error into one that points at the offending
+    /// value using a JSON pointer, e.g. `/releases/0/version`.
+    fn invalid_document(path: &serde_path_to_error::Path, message: String) -> anyhow::Error {
+        let mut pointer = String::new();
+        for segment in path.iter() {
+            let token = match segment {
+                Segment::Seq { index } => index.to_string(),
+                Segment::Map { key } => key.replace('~', "~0").replace('/', "~1"),
+                Segment::Enum { variant } => variant.clone(),
+                Segment::Unknown => continue,
+            };
+
+            pointer.push('/');
+            pointer.push_str(&token);
+        }
+
+        if pointer.is_empty() {
+            pointer.push('/');
+        }
+
+        ChangelogParserError::InvalidDocument { pointer, message }.into()
+    }
+
+    fn get_format_from_buffer(buffer: &str) -> Result<ChangelogFormat> {
         let first_char = match buffer.chars().next() {
             Some(first_char) => first_char,
             _ => {
@@ -240,7 +501,7 @@ impl ChangelogParser {
             }
         };
 
-        let first_line: String = buffer.chars().take_while(|&c| c != '\n').collect();
+        let first_line = buffer.lines().next().unwrap_or_default();
         let mut format: Option<ChangelogFormat> = match first_char {
             '{' => Some(ChangelogFormat::Json),
             '#' => Some(ChangelogFormat::Markdown),
@@ -258,3 +519,16 @@ impl ChangelogParser {
         }
     }
 }
+
+impl FromStr for Changelog<'static> {
+    type Err = anyhow::Error;
+
+    /// Parses a changelog with the default parser.
+    fn from_str(s: &str) -> Result<Self> {
+        ChangelogParser::default().parse_str(s).map(Changelog::into_owned)
+    }
+}
+
+fn strip_bom(buffer: &str) -> &str {
+    buffer.strip_prefix('\u{feff}').unwrap_or(buffer)
+}
@@ -0,0 +1,228 @@
+use crate::changelog::{Changelog, Release};
+use crate::render::DEFAULT_SECTION_ORDER;
+use chrono::{Datelike, NaiveDate};
+use derive_getters::Getters;
+use err_derive::Error;
+use std::collections::HashMap;
+
+/// The words of a changelog in a language, and how it writes dates.
+#[derive(Debug, Getters, PartialEq)]
+pub struct Locale {
+    code: &'static str,
+    /// The names of the sections of a release, in the default section order.
+    sections: [&'static str; 6],
+    unreleased: &'static str,
+    yanked: &'static str,
+    /// The `chrono` format release dates are written with. `%B` is written
+    /// with the month names of the locale.
+    date_format: &'static str,
+    months: [&'static str; 12],
+}
+
+pub const EN: Locale = Locale {
+    code: "en",
+    sections: ["Added", "Changed", "Deprecated", "Removed", "Fixed", "Security"],
+    unreleased: "Unreleased",
+    yanked: "YANKED",
+    date_format: "%Y-%m-%d",
+    months: [
+        "January", "February", "March", "April", "May", "June", "July", "August", "September",
+        "October", "November", "December",
+    ],
+};
+
+pub const DE: Locale = Locale {
+    code: "de",
+    sections: ["Hinzugefügt", "Geändert", "Veraltet", "Entfernt", "Behoben", "Sicherheit"],
+    unreleased: "Unveröffentlicht",
+    yanked: "ZURÜCKGEZOGEN",
+    date_format: "%-d. %B %Y",
+    months: [
+        "Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September",
+        "Oktober", "November", "Dezember",
+    ],
+};
+
+pub const FR: Locale = Locale {
+    code: "fr",
+    sections: ["Ajouté", "Modifié", "Déprécié", "Retiré", "Corrigé", "Sécurité"],
+    unreleased: "Non publié",
+    yanked: "ANNULÉ",
+    date_format: "%-d %B %Y",
+    months: [
+        "janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre",
+        "octobre", "novembre", "décembre",
+    ],
+};
+
+pub const ES: Locale = Locale {
+    code: "es",
+    sections: ["Añadido", "Cambiado", "Obsoleto", "Eliminado", "Arreglado", "Seguridad"],
+    unreleased: "Sin publicar",
+    yanked: "RETIRADO",
+    date_format: "%-d de %B de %Y",
+    months: [
+        "enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto", "septiembre",
+        "octubre", "noviembre", "diciembre",
+    ],
+};
+
+pub const JA: Locale = Locale {
+    code: "ja",
+    sections: ["追加", "変更", "非推奨", "削除", "修正", "セキュリティ"],
+    unreleased: "未リリース",
+    yanked: "取り下げ",
+    date_format: "%Y年%-m月%-d日",
+    months: [
+        "1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月",
+    ],
+};
+
+/// The locales changelogs can be written in.
+pub const LOCALES: &[&Locale] = &[&EN, &DE, &FR, &ES, &JA];
+
+impl Locale {
+    /// Finds a locale by its language code, e.g. `de`.
+    pub fn find(code: &str) -> Option<&'static Locale> {
+        LOCALES
+            .iter()
+            .copied()
+            .find(|locale| locale.code.eq_ignore_ascii_case(code))
+    }
+
+    /// The names of the sections of a release, keyed by change type.
+    pub fn section_names(&self) -> HashMap<String, String> {
+        DEFAULT_SECTION_ORDER
+            .iter()
+            .zip(self.sections.iter())
+            .map(|(kind, name)| (kind.to_lowercase(), name.to_string()))
+            .collect()
+    }
+
+    /// Formats a date, writing `%B` with the month names of the locale.
+    pub fn format_date(&self, date: &NaiveDate, format: &str) -> String {
+        let month = self.months[date.month0() as usize];
+
+        date.format(&format.replace("%B", month)).to_string()
+    }
+
+    /// Parses a date, reading `%B` as the month names of the locale.
+    pub fn parse_date(&self, text: &str, format: &str) -> Option<NaiveDate> {
+        if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+            return Some(date);
+        }
+
+        if !format.contains("%B") {
+            return None;
+        }
+
+        // Month names are matched longest first, so e.g. `10月` is not read
+        // as `0月`.
+        let mut months: Vec<(usize, &str)> = self.months.iter().copied().enumerate().collect();
+        months.sort_by_key(|(_, month)| std::cmp::Reverse(month.len()));
+
+        let lowercase = text.to_lowercase();
+        months.into_iter().find_map(|(index, month)| {
+            let start = lowercase.find(&month.to_lowercase())?;
+            let end = start + month.to_lowercase().len();
+            let text = format!("{}{}{}", &lowercase[..start], EN.months[index], &lowercase[end..]);
+
+            NaiveDate::parse_from_str(&text, format).ok()
+        })
+    }
+
+    /// Whether `text` is the word for an unreleased release, in this locale or
+    /// in English.
+    pub fn is_unreleased(&self, text: &str) -> bool {
+        text.eq_ignore_ascii_case(EN.unreleased)
+            || text.to_lowercase() == self.unreleased.to_lowercase()
+    }
+
+    /// Whether `text` is the label of a yanked release, in this locale or in
+    /// English.
+    pub fn is_yanked(&self, text: &str) -> bool {
+        text.eq_ignore_ascii_case(EN.yanked) || text.to_lowercase() == self.yanked.to_lowercase()
+    }
+}
+
+/// A difference between a changelog and its translation.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TranslationMismatch {
+    #[error(display = "release {} is missing", _0)]
+    MissingRelease(String),
+    #[error(display = "release {} is not in the source changelog", _0)]
+    ExtraRelease(String),
+    #[error(
+        display = "release {} has {} {} entries, expected {}",
+        release,
+        found,
+        section,
+        expected
+    )]
+    EntryCount {
+        release: String,
+        section: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// Checks that a translated changelog has the same releases as its source,
+/// with the same number of entries in each section.
+pub fn check_translation(source: &Changelog, translation: &Changelog) -> Vec<TranslationMismatch> {
+    let mut mismatches = Vec::new();
+
+    for release in source.releases() {
+        let name = release_name(release);
+        let translated = translation
+            .releases()
+            .iter()
+            .find(|translated| release_name(translated) == name);
+
+        let translated = match translated {
+            Some(translated) => translated,
+            None => {
+                mismatches.push(TranslationMismatch::MissingRelease(name));
+                continue;
+            }
+        };
+
+        for section in DEFAULT_SECTION_ORDER {
+            let count = |release: &Release| {
+                release
+                    .changes()
+                    .iter()
+                    .filter(|change| change.kind() == *section)
+                    .count()
+            };
+            let (expected, found) = (count(release), count(translated));
+
+            if expected != found {
+                mismatches.push(TranslationMismatch::EntryCount {
+                    release: name.clone(),
+                    section: section.to_string(),
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+
+    for translated in translation.releases() {
+        let name = release_name(translated);
+
+        if !source.releases().iter().any(|release| release_name(release) == name) {
+            mismatches.push(TranslationMismatch::ExtraRelease(name));
+        }
+    }
+
+    mismatches
+}
+
+fn release_name(release: &Release) -> String {
+    match (release.version(), release.invalid_version()) {
+        (Some(version), _) => version.to_string(),
+        (None, Some(version)) => version.to_string(),
+        (None, None) => EN.unreleased.to_string(),
+    }
+}
@@ -1,20 +1,51 @@
 use anyhow::Result;
 use clap::{
     app_from_crate, crate_authors, crate_description, crate_name, crate_version, AppSettings, Arg,
+    ArgMatches, SubCommand,
 };
-use clparse::ChangelogParser;
-use std::io::{self, Read, Write};
+use clparse::changelog::{Changelog, ChangelogError};
+use clparse::config::{Config, DebianConfig, ImportConfig, RpmConfig, WrapMode, WrapOption};
+use clparse::import::{ImportError, ImportFormat, Importer};
+use clparse::locale::{self, Locale, LOCALES};
+use clparse::init::{self, InitError};
+use clparse::render::{LineEnding, RenderOptions, Wrap};
+use clparse::{ChangelogFormat, ChangelogParser, OutputFormat};
+use similar::TextDiff;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, IsTerminal, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use versions::Version;
+
+/// The names of the output formats accepted by `--format`.
+const FORMATS: &[&str] = &[
+    "json", "yaml", "yml", "markdown", "md", "debian", "rpm", "atom", "rss", "asciidoc", "adoc",
+    "rst", "text", "conventional",
+];
 
 pub fn main() -> Result<()> {
     let matches = app_from_crate!()
         .setting(AppSettings::DisableHelpSubcommand)
         .setting(AppSettings::ArgRequiredElseHelp)
+        .setting(AppSettings::SubcommandsNegateReqs)
         .global_setting(AppSettings::ColoredHelp)
+        .arg(
+            Arg::with_name("config")
+                .help("Sets the project configuration file to use. By default, a .clparse.toml file is looked for in the current directory and its ancestors.")
+                .takes_value(true)
+                .global(true)
+                .value_name("CONFIG")
+                .short("c")
+                .long("config"),
+        )
         .arg(
             Arg::with_name("format")
                 .help("Sets the output format of the parsed CHANGELOG [default: markdown]")
                 .takes_value(true)
-                .possible_values(&["json", "yaml", "yml", "markdown", "md"])
+                .possible_values(FORMATS)
                 .short("f")
                 .long("format"),
         )
@@ -22,13 +53,26 @@ pub fn main() -> Result<()> {
             Arg::with_name("separator")
                 .help("Sets the separator character used between version and date in a release heading [default: -]")
                 .takes_value(true)
+                .global(true)
                 .short("s")
                 .long("separator"),
         )
+        .arg(
+            Arg::with_name("date-format")
+                .help("Sets a chrono format used to parse the date of a release heading. Can be used multiple times, formats are tried in order [default: %Y-%m-%d, %Y/%m/%d, %B %d, %Y, %b %d, %Y, %d %B %Y, %d %b %Y]")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .global(true)
+                .short("d")
+                .long("date-format"),
+        )
         .arg(
             Arg::with_name("no-wrap")
-                .help("Disable wrapping of change entries of a release. By default, change entries are wrapped at 80 characters.")
+                .help("Disable wrapping of change entries of a release, writing each paragraph on a single line. Same as --wrap off. By default, change entries are wrapped at 80 characters.")
                 .takes_value(false)
+                .global(true)
+                .conflicts_with_all(&["wrap", "wrap-at"])
                 .short("n")
                 .long("no-wrap"),
         )
@@ -36,9 +80,50 @@ pub fn main() -> Result<()> {
             Arg::with_name("wrap-at")
                 .help("Specify how many characters to wrap change entries at [default: 80]")
                 .takes_value(true)
+                .global(true)
+                .conflicts_with("wrap")
                 .short("w")
                 .long("wrap-at"),
         )
+        .arg(
+            Arg::with_name("wrap")
+                .help("Sets how change entries are wrapped: a column to wrap at, 'preserve' to keep the line breaks entries were written with, or 'off' [default: 80]")
+                .takes_value(true)
+                .global(true)
+                .value_name("MODE")
+                .long("wrap"),
+        )
+        .arg(
+            Arg::with_name("locale")
+                .help("Sets the language the CHANGELOG is written in, used for section names, unreleased and yanked releases, and dates [default: en]")
+                .takes_value(true)
+                .global(true)
+                .possible_values(&LOCALES.iter().map(|locale| locale.code()).collect::<Vec<_>>())
+                .value_name("LOCALE")
+                .long("locale"),
+        )
+        .arg(
+            Arg::with_name("limit")
+                .help("Only parse and output the first N releases of the CHANGELOG, including the unreleased section")
+                .takes_value(true)
+                .value_name("N")
+                .conflicts_with_all(&["write", "check"])
+                .short("l")
+                .long("limit"),
+        )
+        .arg(
+            Arg::with_name("write")
+                .help("Rewrite the CHANGELOG file in place instead of writing to stdout. The output format defaults to the format of the file.")
+                .takes_value(false)
+                .conflicts_with("check")
+                .long("write"),
+        )
+        .arg(
+            Arg::with_name("check")
+                .help("Exit with a non-zero status and print a diff if the CHANGELOG file is not formatted canonically. The output format defaults to the format of the file.")
+                .takes_value(false)
+                .long("check"),
+        )
         .arg(
             Arg::with_name("file")
                 .help("The CHANGELOG file to parse. This should be either a Markdown, JSON, or Yaml representation of a changelog. Use '-' to read from stdin.")
@@ -46,42 +131,616 @@ pub fn main() -> Result<()> {
                 .index(1)
                 .required(true),
         )
+        .subcommand(
+            SubCommand::with_name("schema")
+                .about("Prints the JSON Schema of the JSON and YAML representations of a CHANGELOG"),
+        )
+        .subcommand(
+            SubCommand::with_name("yank")
+                .about("Marks a release as yanked, rewriting the CHANGELOG file in place")
+                .arg(
+                    Arg::with_name("version")
+                        .help("The version of the release to yank")
+                        .value_name("VERSION")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .help("The CHANGELOG file to modify. Use '-' to read from stdin and write to stdout.")
+                        .value_name("FILE")
+                        .index(2)
+                        .default_value("CHANGELOG.md"),
+                )
+                .arg(
+                    Arg::with_name("reason")
+                        .help("Sets the reason the release was yanked")
+                        .takes_value(true)
+                        .short("r")
+                        .long("reason"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("unyank")
+                .about("Removes the yanked mark from a release, rewriting the CHANGELOG file in place")
+                .arg(
+                    Arg::with_name("version")
+                        .help("The version of the release to unyank")
+                        .value_name("VERSION")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .help("The CHANGELOG file to modify. Use '-' to read from stdin and write to stdout.")
+                        .value_name("FILE")
+                        .index(2)
+                        .default_value("CHANGELOG.md"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Creates a new CHANGELOG with the standard Keep a Changelog description and an empty Unreleased section")
+                .arg(
+                    Arg::with_name("title")
+                        .help("Sets the title of the CHANGELOG [default: Changelog]")
+                        .takes_value(true)
+                        .long("title"),
+                )
+                .arg(
+                    Arg::with_name("repo-url")
+                        .help("Sets the URL of the repository release links compare tags in. Defaults to the repo-url of the project configuration, then to the URL of the origin remote of the git repository.")
+                        .takes_value(true)
+                        .value_name("URL")
+                        .long("repo-url"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Sets the format of the CHANGELOG. Defaults to the format of the file extension, or markdown.")
+                        .takes_value(true)
+                        .possible_values(&["markdown", "md", "json", "yaml", "yml"])
+                        .short("f")
+                        .long("format"),
+                )
+                .arg(
+                    Arg::with_name("from-tags")
+                        .help("Adds a release without changes for each version tag of the git repository")
+                        .takes_value(false)
+                        .long("from-tags"),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .help("Overwrite the CHANGELOG file if it exists")
+                        .takes_value(false)
+                        .long("force"),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .help("The CHANGELOG file to create. Use '-' to write to stdout.")
+                        .value_name("FILE")
+                        .index(1)
+                        .default_value("CHANGELOG.md"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Prints a single release of a CHANGELOG")
+                .arg(
+                    Arg::with_name("release")
+                        .help("The release to print: 'latest', 'unreleased', or a version")
+                        .value_name("RELEASE")
+                        .index(1)
+                        .default_value("latest"),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .help("The CHANGELOG file to read. Use '-' to read from stdin.")
+                        .value_name("FILE")
+                        .index(2)
+                        .default_value("CHANGELOG.md"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Sets the output format of the release [default: text]")
+                        .takes_value(true)
+                        .possible_values(FORMATS)
+                        .short("f")
+                        .long("format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Converts a changelog written in another convention into a Keep a Changelog CHANGELOG, written to stdout")
+                .arg(
+                    Arg::with_name("from")
+                        .help("Sets the convention the changelog is written in: GitHub release notes, conventional-changelog output, or entries tagged with their type, e.g. '- [fixed] ...'")
+                        .takes_value(true)
+                        .required(true)
+                        .possible_values(&["github", "conventional", "tagged"])
+                        .long("from"),
+                )
+                .arg(
+                    Arg::with_name("map")
+                        .help("Imports the entries under a heading, or with a tag, as a change type, e.g. 'Performance Improvements=Changed'. Can be used multiple times.")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("HEADING=TYPE")
+                        .short("m")
+                        .long("map"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Sets the output format of the CHANGELOG [default: markdown]")
+                        .takes_value(true)
+                        .possible_values(FORMATS)
+                        .short("f")
+                        .long("format"),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .help("The changelog file to convert. Use '-' to read from stdin.")
+                        .value_name("FILE")
+                        .index(1)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check-translation")
+                .about("Checks that translated CHANGELOGs have the same releases and number of entries per section as the source CHANGELOG. The locale of each file is taken from its name, e.g. CHANGELOG.de.md, falling back to --locale.")
+                .arg(
+                    Arg::with_name("source")
+                        .help("The source language CHANGELOG file")
+                        .value_name("SOURCE")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("translations")
+                        .help("The translated CHANGELOG files")
+                        .value_name("TRANSLATION")
+                        .index(2)
+                        .multiple(true)
+                        .required(true),
+                ),
+        )
         .get_matches();
 
+    match matches.subcommand() {
+        ("schema", Some(_)) => schema(),
+        ("yank", Some(yank_matches)) => yank(&matches, yank_matches, true),
+        ("unyank", Some(unyank_matches)) => yank(&matches, unyank_matches, false),
+        ("init", Some(init_matches)) => init(&matches, init_matches),
+        ("show", Some(show_matches)) => show(&matches, show_matches),
+        ("convert", Some(convert_matches)) => convert(&matches, convert_matches),
+        ("check-translation", Some(check_matches)) => check_translation(&matches, check_matches),
+        _ => parse(&matches),
+    }
+}
+
+fn parse(matches: &ArgMatches) -> Result<()> {
     let file = matches.value_of("file").unwrap();
-    let separator = matches.value_of("separator").unwrap_or("-");
+    let config = load_config(matches)?;
+    let limit = match matches.value_of("limit") {
+        Some(limit) => Some(limit.parse::<usize>()?),
+        None => None,
+    };
+    let input = read_input(file)?;
+    let (changelog, input_format, line_ending) = read_changelog(&config, &input, limit)?;
 
-    let no_wrap = matches.is_present("no-wrap");
-    let wrap_at = matches.value_of("wrap-at").unwrap_or("80");
-    let wrap = match (no_wrap, wrap_at) {
-        (true, _) => None,
-        (false, wrap_at) => Some(wrap_at.parse::<usize>()?),
+    // Files are rewritten in their own format and line endings unless they
+    // are given explicitly.
+    let in_place = matches.is_present("write") || matches.is_present("check");
+    let mut options = if in_place {
+        in_place_options(&config, line_ending)
+    } else {
+        RenderOptions::from_config(&config)
     };
+    let format = match (matches.value_of("format"), in_place) {
+        (Some(format), _) => output_format(format),
+        (None, true) => input_format.into(),
+        (None, false) => config.format().clone().unwrap_or(OutputFormat::Markdown),
+    };
+
+    if matches.is_present("check") {
+        let output = render(&changelog, &format, &options)?;
 
-    let parser = ChangelogParser::new(separator.into(), wrap);
-    let changelog = if file == "-" {
-        let mut buffer = String::new();
-        io::stdin().read_to_string(&mut buffer)?;
+        if output != input {
+            let name = if file == "-" { "<stdin>" } else { file };
+            let diff = TextDiff::from_lines(&input, &output);
 
-        parser.parse_buffer(buffer)?
+            io::stdout().write_all(
+                diff.unified_diff()
+                    .header(name, name)
+                    .to_string()
+                    .as_bytes(),
+            )?;
+            process::exit(1);
+        }
+    } else if matches.is_present("write") {
+        write_changelog(file, &render(&changelog, &format, &options)?)?;
+    } else {
+        options.set_color(use_color());
+
+        let stdout = io::stdout();
+        let mut writer = io::BufWriter::new(stdout.lock());
+
+        changelog.write_to(&mut writer, &format, &options)?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+fn init(matches: &ArgMatches, subcommand_matches: &ArgMatches) -> Result<()> {
+    let file = subcommand_matches.value_of("file").unwrap();
+    if file != "-" && !subcommand_matches.is_present("force") && Path::new(file).exists() {
+        return Err(InitError::FileExists(file.into()).into());
+    }
+
+    let config = load_config(matches)?;
+    let directory = env::current_dir()?;
+    let repo_url = subcommand_matches
+        .value_of("repo-url")
+        .or_else(|| config.repo_url().as_deref());
+    let repo_url = match repo_url {
+        Some(url) => Some(url.trim_end_matches('/').to_string()),
+        None => init::origin_url(&directory),
+    };
+    let tags = if subcommand_matches.is_present("from-tags") {
+        init::version_tags(&directory)?
     } else {
-        parser.parse(file.into())?
+        Vec::new()
     };
 
-    let output = match matches.value_of("format").unwrap_or("markdown") {
-        "json" => {
-            format!("{}", serde_json::to_string_pretty(&changelog)?)
+    let title = subcommand_matches.value_of("title").unwrap_or("Changelog");
+    let changelog = init::changelog(title, repo_url.as_deref(), &tags)?;
+
+    let format = match (subcommand_matches.value_of("format"), Path::new(file).extension()) {
+        (Some(format), _) => output_format(format),
+        (None, Some(extension)) if extension == "json" => OutputFormat::Json,
+        (None, Some(extension)) if extension == "yaml" || extension == "yml" => {
+            OutputFormat::Yaml
         }
-        "yaml" | "yml" => {
-            format!("{}", serde_yaml::to_string(&changelog)?)
+        _ => OutputFormat::Markdown,
+    };
+    let options = RenderOptions::from_config(&config);
+
+    write_changelog(file, &render(&changelog, &format, &options)?)
+}
+
+fn show(matches: &ArgMatches, subcommand_matches: &ArgMatches) -> Result<()> {
+    let file = subcommand_matches.value_of("file").unwrap();
+    let config = load_config(matches)?;
+    let input = read_input(file)?;
+    let (changelog, _, _) = read_changelog(&config, &input, None)?;
+    let mut options = RenderOptions::from_config(&config);
+    options.set_color(use_color());
+
+    let format = subcommand_matches
+        .value_of("format")
+        .map(output_format)
+        .unwrap_or(OutputFormat::Text);
+    let release = match subcommand_matches.value_of("release").unwrap() {
+        "latest" => changelog.latest_release(),
+        "unreleased" => changelog.unreleased(),
+        version => {
+            let parsed = Version::new(version)
+                .ok_or_else(|| ChangelogError::InvalidVersion(version.into()))?;
+
+            changelog.release(parsed)
         }
-        "markdown" | "md" => {
-            format!("{}", &changelog)
+    };
+    let release = release.ok_or_else(|| {
+        ChangelogError::ReleaseNotFound(subcommand_matches.value_of("release").unwrap().into())
+    })?;
+
+    let stdout = io::stdout();
+    let mut writer = io::BufWriter::new(stdout.lock());
+
+    release.write_to(&mut writer, &format, &options)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn convert(matches: &ArgMatches, subcommand_matches: &ArgMatches) -> Result<()> {
+    let mut config = load_config(matches)?;
+
+    if let Some(mappings) = subcommand_matches.values_of("map") {
+        let import = config.import_mut().get_or_insert_with(ImportConfig::default);
+        let headings = import.headings_mut().get_or_insert_with(HashMap::new);
+
+        for mapping in mappings {
+            let (heading, kind) = mapping
+                .split_once('=')
+                .ok_or_else(|| ImportError::InvalidMapping(mapping.into()))?;
+            headings.insert(heading.trim().into(), kind.trim().into());
         }
-        _ => unreachable!(),
+        config.validate()?;
+    }
+
+    let file = subcommand_matches.value_of("file").unwrap();
+    let buffer = read_input(file)?.replace("\r\n", "\n");
+
+    let format = subcommand_matches.value_of("from").unwrap().parse::<ImportFormat>()?;
+    let imported = Importer::from_config(format, &config).import(&buffer)?;
+    for heading in imported.unmapped() {
+        eprintln!(
+            "warning: skipped the entries under '{}', which is not mapped to a change type",
+            heading
+        );
+    }
+
+    let format = match subcommand_matches.value_of("format") {
+        Some(format) => output_format(format),
+        None => config.format().clone().unwrap_or(OutputFormat::Markdown),
     };
+    let mut options = RenderOptions::from_config(&config);
+    options.set_color(use_color());
+
+    let stdout = io::stdout();
+    let mut writer = io::BufWriter::new(stdout.lock());
+
+    imported.changelog().write_to(&mut writer, &format, &options)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn schema() -> Result<()> {
+    let schema = serde_json::to_string_pretty(&Changelog::json_schema())?;
 
-    io::stdout().write_all(output.as_bytes())?;
+    io::stdout().write_all(format!("{}\n", schema).as_bytes())?;
 
     Ok(())
 }
+
+fn yank(matches: &ArgMatches, subcommand_matches: &ArgMatches, yanked: bool) -> Result<()> {
+    let file = subcommand_matches.value_of("file").unwrap();
+    let config = load_config(matches)?;
+    let input = read_input(file)?;
+    let (mut changelog, format, line_ending) = read_changelog(&config, &input, None)?;
+    let options = in_place_options(&config, line_ending);
+
+    let version = subcommand_matches.value_of("version").unwrap();
+    let parsed = Version::new(version)
+        .ok_or_else(|| ChangelogError::InvalidVersion(version.into()))?;
+    let release = changelog
+        .release_mut(parsed)
+        .ok_or_else(|| ChangelogError::ReleaseNotFound(version.into()))?;
+
+    release.yank(yanked);
+    if let Some(reason) = subcommand_matches.value_of("reason") {
+        release.set_yank_reason(reason.to_string());
+    }
+
+    write_changelog(file, &render(&changelog, &format.into(), &options)?)
+}
+
+fn check_translation(matches: &ArgMatches, subcommand_matches: &ArgMatches) -> Result<()> {
+    let config = load_config(matches)?;
+    let source_file = subcommand_matches.value_of("source").unwrap();
+    let source = read_translation(&config, source_file)?;
+
+    let mut mismatched = false;
+    for file in subcommand_matches.values_of("translations").unwrap() {
+        let translation = read_translation(&config, file)?;
+
+        for mismatch in locale::check_translation(&source, &translation) {
+            println!("{}: {}", file, mismatch);
+            mismatched = true;
+        }
+    }
+
+    if mismatched {
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Parses a CHANGELOG in the locale named by its file name, e.g.
+/// CHANGELOG.de.md, or the configured locale.
+fn read_translation(config: &Config, file: &str) -> Result<Changelog<'static>> {
+    let mut parser = ChangelogParser::from_config(config);
+    let locale = Path::new(file)
+        .file_stem()
+        .map(Path::new)
+        .and_then(Path::extension)
+        .and_then(|code| Locale::find(&code.to_string_lossy()));
+
+    if let Some(locale) = locale {
+        parser.set_locale(locale);
+    }
+
+    parser.parse(file.into())
+}
+
+/// Loads the project configuration, overriding its options with any passed on
+/// the command line.
+fn load_config(matches: &ArgMatches) -> Result<Config> {
+    let mut config = match matches.value_of("config") {
+        Some(path) => Config::from_path(Path::new(path))?,
+        None => Config::discover(&env::current_dir()?)?
+            .map(|(_, config)| config)
+            .unwrap_or_default(),
+    };
+
+    if let Some(separator) = matches.value_of("separator") {
+        config.set_separator(separator.into());
+    }
+
+    if let Some(locale) = matches.value_of("locale") {
+        config.set_locale(locale.into());
+    }
+
+    if let Some(date_formats) = matches.values_of("date-format") {
+        config.set_date_formats(date_formats.map(String::from).collect());
+    }
+
+    // Like dch, the Debian maintainer defaults to DEBFULLNAME and DEBEMAIL.
+    if let (Ok(name), Ok(email)) = (env::var("DEBFULLNAME"), env::var("DEBEMAIL")) {
+        let debian = config.debian_mut().get_or_insert_with(DebianConfig::default);
+
+        if debian.maintainer().is_none() {
+            debian.set_maintainer(format!("{} <{}>", name, email));
+        }
+    }
+
+    // Like rpmdev-packager, the RPM packager defaults to RPM_PACKAGER.
+    if let Ok(packager) = env::var("RPM_PACKAGER") {
+        let rpm = config.rpm_mut().get_or_insert_with(RpmConfig::default);
+
+        if rpm.packager().is_none() {
+            rpm.set_packager(packager);
+        }
+    }
+
+    let wrap = match (matches.value_of("wrap"), matches.value_of("wrap-at")) {
+        (Some(wrap), _) | (None, Some(wrap)) => Some(wrap.parse::<Wrap>()?),
+        _ if matches.is_present("no-wrap") => Some(Wrap::Off),
+        _ => None,
+    };
+
+    match wrap {
+        Some(Wrap::At(width)) => {
+            config.set_wrap(WrapOption::Enabled(true)).set_wrap_at(width);
+        }
+        Some(Wrap::Preserve) => {
+            config.set_wrap(WrapOption::Mode(WrapMode::Preserve));
+        }
+        Some(Wrap::Off) => {
+            config.set_wrap(WrapOption::Enabled(false));
+        }
+        None => (),
+    }
+
+    Ok(config)
+}
+
+/// Parses the contents of a changelog, returning it along with its format and
+/// line endings.
+fn read_changelog<'a>(
+    config: &Config,
+    buffer: &'a str,
+    limit: Option<usize>,
+) -> Result<(Changelog<'a>, ChangelogFormat, LineEnding)> {
+    let parser = ChangelogParser::from_config(config);
+
+    let format = ChangelogParser::detect_format(buffer)?;
+    let changelog = match limit {
+        Some(limit) if buffer.contains('\r') => parser
+            .parse_head(&buffer.replace("\r\n", "\n"), limit)?
+            .into_owned(),
+        Some(limit) => parser.parse_head(buffer, limit)?,
+        None => parser.parse_bytes(buffer.as_bytes())?,
+    };
+    let line_ending = if buffer.contains("\r\n") {
+        LineEnding::Crlf
+    } else {
+        LineEnding::Lf
+    };
+
+    let lint = config.lint().clone().unwrap_or_default();
+    for diagnostic in changelog.diagnostics().iter().filter(|d| lint.reports(d)) {
+        eprintln!("warning: {}", diagnostic);
+    }
+
+    Ok((changelog, format, line_ending))
+}
+
+/// Reads the contents of `file`, or of stdin when `file` is `-`.
+fn read_input(file: &str) -> Result<String> {
+    let mut buffer = Vec::new();
+    if file == "-" {
+        io::stdin().read_to_end(&mut buffer)?;
+    } else {
+        buffer = fs::read(file)?;
+    }
+
+    Ok(String::from_utf8(buffer)?)
+}
+
+/// Creates the options a changelog is rewritten in place with, keeping the
+/// line endings it was read with unless they are configured.
+fn in_place_options(config: &Config, line_ending: LineEnding) -> RenderOptions {
+    let mut options = RenderOptions::from_config(config);
+    if config.line_ending().is_none() {
+        options.set_line_ending(line_ending);
+    }
+
+    options
+}
+
+/// Writes `contents` to `file`, or to stdout when `file` is `-`.
+///
+/// The file is replaced atomically by writing to a temporary file next to it
+/// and renaming it over the original.
+fn write_changelog(file: &str, contents: &str) -> Result<()> {
+    if file == "-" {
+        io::stdout().write_all(contents.as_bytes())?;
+        return Ok(());
+    }
+
+    let path = Path::new(file);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp = TempFile(path.with_file_name(format!(".{}.clparse.tmp", file_name)));
+
+    let mut temp_file = File::create(&temp.0)?;
+    temp_file.write_all(contents.as_bytes())?;
+    temp_file.sync_all()?;
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(&temp.0, metadata.permissions())?;
+    }
+
+    fs::rename(&temp.0, path)?;
+    mem::forget(temp);
+
+    Ok(())
+}
+
+/// A temporary file, removed when dropped unless forgotten once it has been
+/// renamed into place.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn render(changelog: &Changelog, format: &OutputFormat, options: &RenderOptions) -> Result<String> {
+    let mut output = Vec::new();
+    changelog.write_to(&mut output, format, options)?;
+
+    Ok(String::from_utf8(output)?)
+}
+
+/// Maps the name of an output format passed to `--format` to the format.
+fn output_format(name: &str) -> OutputFormat {
+    match name {
+        "json" => OutputFormat::Json,
+        "yaml" | "yml" => OutputFormat::Yaml,
+        "debian" => OutputFormat::Debian,
+        "rpm" => OutputFormat::Rpm,
+        "atom" => OutputFormat::Atom,
+        "rss" => OutputFormat::Rss,
+        "asciidoc" | "adoc" => OutputFormat::AsciiDoc,
+        "rst" => OutputFormat::Rst,
+        "text" => OutputFormat::Text,
+        "conventional" => OutputFormat::Conventional,
+        _ => OutputFormat::Markdown,
+    }
+}
+
+/// Whether text output is colored: only when writing to a terminal, and not
+/// when the NO_COLOR environment variable is set.
+fn use_color() -> bool {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+
+    !no_color && io::stdout().is_terminal()
+}
@@ -0,0 +1,610 @@
+use crate::changelog::{Change, Footnote, Release, ReleaseBuilder};
+use crate::render::EmphasisStyle;
+use crate::{ChangelogParser, ChangelogParserError};
+use anyhow::Result;
+use pulldown_cmark::{CowStr, Event, LinkType, OffsetIter, Options, Parser, Tag};
+use std::borrow::Cow;
+use std::mem;
+use std::ops::Range;
+
+#[derive(Clone, Debug)]
+enum ChangelogSection {
+    None,
+    Title,
+    Description,
+    ReleaseHeader,
+    Release,
+    ChangesetHeader,
+    Changeset(String),
+}
+
+/// An iterator over the releases of a changelog.
+///
+/// Releases of a markdown changelog are parsed one at a time as the iterator
+/// is advanced, so stopping early skips the work of parsing the rest of the
+/// document.
+pub struct Releases<'a> {
+    inner: ReleasesInner<'a>,
+}
+
+enum ReleasesInner<'a> {
+    Markdown(Box<MarkdownReleases<'a, 'a>>),
+    Parsed(std::vec::IntoIter<Release<'a>>),
+}
+
+impl<'a> Releases<'a> {
+    pub(crate) fn markdown(releases: MarkdownReleases<'a, 'a>) -> Self {
+        Self {
+            inner: ReleasesInner::Markdown(Box::new(releases)),
+        }
+    }
+
+    pub(crate) fn parsed(releases: Vec<Release<'a>>) -> Self {
+        Self {
+            inner: ReleasesInner::Parsed(releases.into_iter()),
+        }
+    }
+}
+
+impl<'a> Iterator for Releases<'a> {
+    type Item = Result<Release<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            ReleasesInner::Markdown(releases) => releases.next(),
+            ReleasesInner::Parsed(releases) => releases.next().map(Ok),
+        }
+    }
+}
+
+/// Parses a markdown changelog, reading the title and description up front and
+/// each release on demand.
+pub(crate) struct MarkdownReleases<'p, 'a> {
+    parser: &'p ChangelogParser,
+    markdown: &'a str,
+    events: OffsetIter<'a>,
+    section: ChangelogSection,
+    release: ReleaseBuilder<'a>,
+    changeset: Vec<Change<'a>>,
+    release_notes: String,
+    accumulator: String,
+    footnote: Option<(Cow<'a, str>, String)>,
+    footnotes: Vec<Footnote<'a>>,
+    /// The lists an item is nested in, with the next number of ordered lists
+    /// and the width of the marker of their current item.
+    lists: Vec<(Option<usize>, usize)>,
+    finished: bool,
+}
+
+impl<'p, 'a> MarkdownReleases<'p, 'a> {
+    pub(crate) fn new(parser: &'p ChangelogParser, markdown: &'a str) -> Self {
+        let options = Options::ENABLE_FOOTNOTES | Options::ENABLE_STRIKETHROUGH;
+
+        Self {
+            parser,
+            markdown,
+            events: Parser::new_ext(markdown, options).into_offset_iter(),
+            section: ChangelogSection::None,
+            release: ReleaseBuilder::default(),
+            changeset: Vec::new(),
+            release_notes: String::new(),
+            accumulator: String::new(),
+            footnote: None,
+            footnotes: Vec::new(),
+            lists: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// The footnote definitions read so far.
+    pub(crate) fn into_footnotes(self) -> Vec<Footnote<'a>> {
+        self.footnotes
+    }
+
+    /// Reads the title and description of the changelog, stopping at the
+    /// heading of the first release.
+    pub(crate) fn read_head(&mut self) -> (Cow<'a, str>, Cow<'a, str>) {
+        let mut title = String::new();
+        let mut title_source = "";
+        let mut head_end = self.markdown.len();
+        let mut description_links = String::new();
+        let mut link_accumulator = String::new();
+
+        while let Some((event, range)) = self.events.next() {
+            if self.push_footnote(&event, &range) {
+                continue;
+            }
+
+            match event {
+                Event::Start(Tag::Header(1)) => self.section = ChangelogSection::Title,
+                Event::End(Tag::Header(1)) => {
+                    title_source = &self.markdown[range];
+                    self.section = ChangelogSection::Description;
+                }
+                Event::Start(Tag::Header(2)) => {
+                    head_end = range.start;
+                    self.section = ChangelogSection::ReleaseHeader;
+                    break;
+                }
+
+                // Collapsed links are collected so their definitions can be
+                // kept with the description.
+                Event::Start(Tag::Link(LinkType::Collapsed, _, _)) => {
+                    self.accumulator.push('[');
+                    link_accumulator = String::from("[");
+                }
+                Event::End(Tag::Link(LinkType::Collapsed, href, _)) => {
+                    self.accumulator.push_str("][]");
+                    link_accumulator.push_str("]: ");
+                    link_accumulator.push_str(&href);
+                    link_accumulator.push('\n');
+                    description_links.push_str(&link_accumulator);
+                    link_accumulator.clear();
+                }
+
+                Event::Text(_) => match self.section {
+                    ChangelogSection::Title => {
+                        title.push_str(source_text(self.markdown, &range))
+                    }
+                    ChangelogSection::Description => {
+                        let text = source_text(self.markdown, &range);
+                        self.accumulator.push_str(text);
+
+                        if !link_accumulator.is_empty() {
+                            link_accumulator.push_str(text);
+                        }
+                    }
+                    _ => (),
+                },
+                event => match self.section {
+                    ChangelogSection::Title => push_inline(&mut title, event, self.markdown, &range),
+                    _ => push_inline(&mut self.accumulator, event, self.markdown, &range),
+                },
+            }
+        }
+
+        let mut description = match self.section {
+            ChangelogSection::Description | ChangelogSection::ReleaseHeader => {
+                mem::take(&mut self.accumulator)
+            }
+            _ => String::new(),
+        };
+
+        if !description_links.is_empty() {
+            description.push_str(&description_links);
+            description.push('\n');
+        }
+
+        let description = borrow_from(&self.markdown[..head_end], &description);
+        (borrow_from(title_source, &title), description)
+    }
+
+    /// Collects the contents of footnote definitions, which may appear
+    /// anywhere in the document. Returns whether the event was consumed.
+    fn push_footnote(&mut self, event: &Event<'a>, range: &Range<usize>) -> bool {
+        match event {
+            Event::Start(Tag::FootnoteDefinition(name)) => {
+                let outer = mem::take(&mut self.accumulator);
+                self.footnote = Some((cow(name.clone()), outer));
+            }
+            Event::End(Tag::FootnoteDefinition(_)) => {
+                if let Some((name, outer)) = self.footnote.take() {
+                    let text = mem::replace(&mut self.accumulator, outer);
+                    let text = borrow_from(&self.markdown[range.clone()], text.trim());
+                    self.footnotes.push(Footnote::new(name, text));
+                }
+            }
+            Event::Text(_) if self.footnote.is_some() => {
+                self.accumulator.push_str(source_text(self.markdown, range))
+            }
+            event if self.footnote.is_some() => {
+                push_inline(&mut self.accumulator, event.clone(), self.markdown, range)
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Starts a nested list item on a new line, indented to line up with the
+    /// text of the item it is nested in.
+    fn push_nested_item(&mut self) {
+        let depth = self.lists.len();
+        let indent: usize = self.lists[1..depth - 1].iter().map(|(_, width)| width).sum();
+        let marker = match &mut self.lists[depth - 1].0 {
+            Some(number) => {
+                *number += 1;
+                format!("{}. ", *number - 1)
+            }
+            None => "- ".to_string(),
+        };
+
+        self.lists[depth - 1].1 = marker.len();
+
+        let trimmed = self.accumulator.trim_end().len();
+        self.accumulator.truncate(trimmed);
+        self.accumulator.push('\n');
+        self.accumulator.push_str(&" ".repeat(indent));
+        self.accumulator.push_str(&marker);
+    }
+
+    fn build_release(&mut self) -> Result<Release<'a>> {
+        if let ChangelogSection::Release = self.section {
+            self.release_notes = self.accumulator.trim().to_string();
+        }
+
+        self.release.changes(mem::take(&mut self.changeset));
+
+        let mut release = mem::take(&mut self.release)
+            .build()
+            .map_err(ChangelogParserError::ErrorBuildingRelease)?;
+
+        // The text between the heading of a yanked release and its changes is
+        // the reason it was yanked.
+        if *release.yanked() && !self.release_notes.is_empty() {
+            release.set_yank_reason(mem::take(&mut self.release_notes));
+        }
+        self.release_notes.clear();
+
+        Ok(release)
+    }
+
+    fn next_release(&mut self) -> Result<Option<Release<'a>>> {
+        while let Some((event, range)) = self.events.next() {
+            if self.push_footnote(&event, &range) {
+                continue;
+            }
+
+            match event {
+                // Headings.
+                Event::Start(Tag::Header(2)) => {
+                    let release = match self.section {
+                        ChangelogSection::Release | ChangelogSection::Changeset(_) => {
+                            Some(self.build_release()?)
+                        }
+                        _ => None,
+                    };
+
+                    self.accumulator.clear();
+                    self.lists.clear();
+                    self.section = ChangelogSection::ReleaseHeader;
+
+                    if release.is_some() {
+                        return Ok(release);
+                    }
+                }
+                Event::End(Tag::Header(2)) => {
+                    if let ChangelogSection::ReleaseHeader = self.section {
+                        self.parser
+                            .parse_release_header(&mut self.release, &mut self.accumulator);
+                        self.section = ChangelogSection::Release;
+                    }
+                }
+                Event::Start(Tag::Header(3)) => {
+                    if let ChangelogSection::Release = self.section {
+                        self.release_notes = self.accumulator.trim().to_string();
+                    }
+
+                    self.accumulator.clear();
+                    self.lists.clear();
+                    self.section = ChangelogSection::ChangesetHeader;
+                }
+                Event::End(Tag::Header(3)) => {
+                    if let ChangelogSection::ChangesetHeader = self.section {
+                        let name = mem::take(&mut self.accumulator);
+                        self.section = ChangelogSection::Changeset(name.trim().to_string());
+                    }
+                }
+
+                // Links in a release heading link to the release, e.g.
+                // `## [1.0.0] - 2019-06-20` or `## [1.0.0](https://...)`.
+                Event::Start(Tag::Link(link_type, _, _))
+                    if is_release_link(&self.section, link_type) =>
+                {
+                    self.accumulator.push('[')
+                }
+                Event::End(Tag::Link(link_type, href, _))
+                    if is_release_link(&self.section, link_type) =>
+                {
+                    self.accumulator.push(']');
+                    self.release.link(cow(href));
+                }
+
+                // Items. Nested lists are kept in the description of the item
+                // they belong to.
+                Event::Start(Tag::List(start)) => {
+                    if let ChangelogSection::Changeset(_) = self.section {
+                        self.lists.push((start, 0));
+                    }
+                }
+                Event::End(Tag::List(_)) => {
+                    self.lists.pop();
+                }
+                Event::Start(Tag::Item) if self.lists.len() > 1 => self.push_nested_item(),
+                Event::End(Tag::Item) if self.lists.len() > 1 => (),
+                Event::End(Tag::Item) => {
+                    if let ChangelogSection::Changeset(name) = &self.section {
+                        let item = &self.markdown[range];
+                        let description = borrow_from(item, self.accumulator.trim_end());
+                        self.accumulator.clear();
+
+                        let kind = self.parser.section_kind(name);
+                        self.changeset.push(Change::new(kind, description)?);
+                    }
+                }
+
+                // Text.
+                Event::Text(text) => match self.section {
+                    ChangelogSection::ChangesetHeader => self.accumulator.push_str(&text),
+                    ChangelogSection::Changeset(_)
+                    | ChangelogSection::ReleaseHeader
+                    | ChangelogSection::Release => self
+                        .accumulator
+                        .push_str(source_text(self.markdown, &range)),
+                    _ => (),
+                },
+                event => push_inline(&mut self.accumulator, event, self.markdown, &range),
+            }
+        }
+
+        // The last release ends with the document. A changelog without any
+        // releases gets an empty unreleased section.
+        self.finished = true;
+
+        Ok(Some(self.build_release()?))
+    }
+}
+
+fn is_release_link(section: &ChangelogSection, link_type: LinkType) -> bool {
+    match (section, link_type) {
+        (ChangelogSection::ReleaseHeader, LinkType::Autolink)
+        | (ChangelogSection::ReleaseHeader, LinkType::Email) => false,
+        (ChangelogSection::ReleaseHeader, _) => true,
+        _ => false,
+    }
+}
+
+impl<'a> Iterator for MarkdownReleases<'_, 'a> {
+    type Item = Result<Release<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        match self.next_release() {
+            Ok(release) => release.map(Ok),
+            Err(err) => {
+                self.finished = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Cuts a markdown changelog down to its head and first `count` releases,
+/// keeping the link reference definitions of the whole document.
+///
+/// This saves the markdown parser from processing the rest of the document.
+pub(crate) fn truncate(markdown: &str, count: usize) -> Cow<'_, str> {
+    let mut releases = 0;
+    let mut in_code_block = false;
+    let mut offset = 0;
+
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+
+        if !in_code_block && line.starts_with("## ") {
+            releases += 1;
+
+            if releases > count {
+                let (head, rest) = markdown.split_at(offset);
+                let mut truncated = head.to_string();
+
+                for definition in rest.lines().filter(|line| is_link_definition(line)) {
+                    truncated.push('\n');
+                    truncated.push_str(definition);
+                }
+
+                return Cow::Owned(truncated);
+            }
+        }
+
+        offset += line.len();
+    }
+
+    Cow::Borrowed(markdown)
+}
+
+/// Borrows `text` from `source` where it appears there verbatim, which it
+/// does unless inline markup was rewritten, and copies it otherwise.
+fn borrow_from<'a>(source: &'a str, text: &str) -> Cow<'a, str> {
+    match source.find(text) {
+        Some(start) => Cow::Borrowed(&source[start..start + text.len()]),
+        None => Cow::Owned(text.to_string()),
+    }
+}
+
+fn cow(text: CowStr<'_>) -> Cow<'_, str> {
+    match text {
+        CowStr::Borrowed(text) => Cow::Borrowed(text),
+        text => Cow::Owned(text.to_string()),
+    }
+}
+
+fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[') && line.contains("]: ")
+}
+
+/// Appends the markdown for an inline event to `accumulator`, keeping the
+/// markup of the source document where possible.
+fn push_inline(accumulator: &mut String, event: Event, markdown: &str, range: &Range<usize>) {
+    let source = &markdown[range.clone()];
+
+    match event {
+        Event::Text(_) => accumulator.push_str(source_text(markdown, range)),
+
+        // Links and images. Reference links are written inline, as their
+        // definitions are not kept.
+        Event::Start(Tag::Link(LinkType::Autolink, _, _))
+        | Event::Start(Tag::Link(LinkType::Email, _, _)) => accumulator.push('<'),
+        Event::End(Tag::Link(LinkType::Autolink, _, _))
+        | Event::End(Tag::Link(LinkType::Email, _, _)) => accumulator.push('>'),
+        Event::Start(Tag::Link(_, _, _)) => accumulator.push('['),
+        Event::Start(Tag::Image(_, _, _)) => accumulator.push_str("!["),
+        Event::End(Tag::Link(_, href, title)) | Event::End(Tag::Image(_, href, title)) => {
+            push_destination(accumulator, &href, &title)
+        }
+
+        // Line breaks.
+        Event::SoftBreak => accumulator.push('\n'),
+        Event::HardBreak if source.ends_with('\n') => accumulator.push_str(source),
+        Event::HardBreak => accumulator.push_str("\\\n"),
+        Event::End(Tag::Paragraph) => accumulator.push_str("\n\n"),
+
+        // Inline code. Code spans broken over lines are joined.
+        Event::Code(_) if source.starts_with('`') && !source.contains('\n') => {
+            accumulator.push_str(source)
+        }
+        Event::Code(text) => push_code(accumulator, &text),
+
+        // Inline HTML and footnotes.
+        Event::InlineHtml(html) | Event::Html(html) => accumulator.push_str(&html),
+        Event::FootnoteReference(name) => {
+            accumulator.push_str("[^");
+            accumulator.push_str(&name);
+            accumulator.push(']');
+        }
+
+        // Text formatting, keeping the delimiters of the source.
+        Event::Start(Tag::Strong) => accumulator.push_str(delimiters(source, "**").0),
+        Event::End(Tag::Strong) => accumulator.push_str(delimiters(source, "**").1),
+        Event::Start(Tag::Emphasis) => accumulator.push_str(delimiters(source, "_").0),
+        Event::End(Tag::Emphasis) => accumulator.push_str(delimiters(source, "_").1),
+        Event::Start(Tag::Strikethrough) => accumulator.push_str(delimiters(source, "~~").0),
+        Event::End(Tag::Strikethrough) => accumulator.push_str(delimiters(source, "~~").1),
+
+        _ => (),
+    }
+}
+
+/// The source of a text event, including the backslash if it was escaped.
+fn source_text<'a>(markdown: &'a str, range: &Range<usize>) -> &'a str {
+    let backslashes = markdown[..range.start]
+        .bytes()
+        .rev()
+        .take_while(|b| *b == b'\\')
+        .count();
+    let escaped = backslashes % 2 == 1
+        && markdown[range.clone()]
+            .starts_with(|c: char| c.is_ascii_punctuation());
+
+    if escaped {
+        &markdown[range.start - 1..range.end]
+    } else {
+        &markdown[range.clone()]
+    }
+}
+
+/// The opening and closing delimiters of an emphasized span of source, e.g.
+/// `__` for `__strong__`, or `default` if they can't be determined.
+fn delimiters<'a>(source: &'a str, default: &'a str) -> (&'a str, &'a str) {
+    let width = default.len();
+    let delimiter = default.as_bytes()[0];
+    let is_delimiter = |b: &u8| *b == delimiter || (delimiter != b'~' && (*b == b'*' || *b == b'_'));
+
+    let bytes = source.as_bytes();
+    if bytes.len() >= width * 2
+        && bytes[..width].iter().all(is_delimiter)
+        && bytes[bytes.len() - width..].iter().all(is_delimiter)
+    {
+        (&source[..width], &source[source.len() - width..])
+    } else {
+        (default, default)
+    }
+}
+
+/// Appends a link destination and title, e.g. `](https://... "title")`.
+fn push_destination(accumulator: &mut String, href: &str, title: &str) {
+    accumulator.push_str("](");
+    if href.contains(' ') || href.is_empty() {
+        accumulator.push('<');
+        accumulator.push_str(href);
+        accumulator.push('>');
+    } else {
+        accumulator.push_str(href);
+    }
+
+    if !title.is_empty() {
+        accumulator.push_str(" \"");
+        accumulator.push_str(&title.replace('"', "\\\""));
+        accumulator.push('"');
+    }
+    accumulator.push(')');
+}
+
+/// Appends an inline code span, fenced with enough backticks to contain the
+/// ones in `code`.
+fn push_code(accumulator: &mut String, code: &str) {
+    let longest = code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest + 1);
+    let padding = if code.starts_with('`') || code.ends_with('`') { " " } else { "" };
+
+    accumulator.push_str(&fence);
+    accumulator.push_str(padding);
+    accumulator.push_str(code);
+    accumulator.push_str(padding);
+    accumulator.push_str(&fence);
+}
+
+/// Rewrites the emphasis and strong emphasis delimiters of `text` in the given
+/// style, leaving the rest of the text untouched.
+pub(crate) fn restyle_emphasis<'a>(text: &'a str, style: &EmphasisStyle) -> Cow<'a, str> {
+    let (from, to) = match style {
+        EmphasisStyle::Underscore => (b'*', b'_'),
+        EmphasisStyle::Asterisk => (b'_', b'*'),
+    };
+
+    if !text.as_bytes().contains(&from) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut bytes = text.as_bytes().to_vec();
+    for (event, range) in Parser::new(text).into_offset_iter() {
+        let width = match event {
+            Event::Start(Tag::Emphasis) => 1,
+            Event::Start(Tag::Strong) => 2,
+            _ => continue,
+        };
+
+        if bytes[range.start] != from {
+            continue;
+        }
+
+        // Underscores don't delimit emphasis inside of words, so emphasis
+        // touching a word is left as is.
+        let touches_word = text[..range.start]
+            .chars()
+            .next_back()
+            .into_iter()
+            .chain(text[range.end..].chars().next())
+            .any(char::is_alphanumeric);
+        if to == b'_' && touches_word {
+            continue;
+        }
+
+        for offset in 0..width {
+            bytes[range.start + offset] = to;
+            bytes[range.end - 1 - offset] = to;
+        }
+    }
+
+    Cow::Owned(String::from_utf8(bytes).expect("delimiters are replaced with ASCII"))
+}
@@ -0,0 +1,547 @@
+use crate::changelog::{Changelog, Footnote, Release};
+use crate::render::RenderOptions;
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag};
+use std::collections::HashSet;
+use std::fmt;
+
+/// The lightweight markup languages markdown is converted to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Syntax {
+    AsciiDoc,
+    Rst,
+}
+
+/// A block of converted markdown.
+struct Block {
+    /// Whether each list the block is nested in is ordered, outermost first.
+    lists: Vec<bool>,
+    /// Whether the block starts a list item.
+    first: bool,
+    kind: BlockKind,
+}
+
+enum BlockKind {
+    Paragraph(String),
+    Code { language: String, text: String },
+}
+
+/// Converts markdown, such as change entries, to AsciiDoc or reStructuredText.
+struct Converter<'a> {
+    syntax: Syntax,
+    footnotes: &'a [Footnote<'a>],
+    /// The footnotes referenced so far. AsciiDoc footnotes are defined where
+    /// they are first referenced.
+    referenced: HashSet<String>,
+}
+
+impl<'a> Converter<'a> {
+    fn new(syntax: Syntax, footnotes: &'a [Footnote<'a>]) -> Self {
+        Self {
+            syntax,
+            footnotes,
+            referenced: HashSet::new(),
+        }
+    }
+
+    /// Converts markdown to blocks. `lists` are the lists the markdown is an
+    /// item of, e.g. a change entry is an item of an unordered list.
+    fn blocks(&mut self, markdown: &str, lists: Vec<bool>) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut lists = lists;
+        let mut first = !lists.is_empty();
+        let mut text = String::new();
+        let mut link: Option<(LinkType, String, usize)> = None;
+        let mut code: Option<String> = None;
+
+        let flush = |blocks: &mut Vec<Block>, lists: &[bool], first: &mut bool, text: &mut String| {
+            if !text.trim().is_empty() {
+                blocks.push(Block {
+                    lists: lists.to_vec(),
+                    first: *first,
+                    kind: BlockKind::Paragraph(text.trim().to_string()),
+                });
+                *first = false;
+            }
+            text.clear();
+        };
+
+        let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_FOOTNOTES;
+        for event in Parser::new_ext(markdown, options) {
+            match event {
+                Event::End(Tag::Paragraph) | Event::End(Tag::Header(_)) | Event::End(Tag::Item) => {
+                    flush(&mut blocks, &lists, &mut first, &mut text)
+                }
+                Event::Start(Tag::Item) => {
+                    flush(&mut blocks, &lists, &mut first, &mut text);
+                    first = true;
+                }
+                Event::Start(Tag::List(start)) => {
+                    flush(&mut blocks, &lists, &mut first, &mut text);
+                    lists.push(start.is_some());
+                }
+                Event::End(Tag::List(_)) => {
+                    flush(&mut blocks, &lists, &mut first, &mut text);
+                    lists.pop();
+                }
+
+                // Code blocks.
+                Event::Start(Tag::CodeBlock(language)) => {
+                    flush(&mut blocks, &lists, &mut first, &mut text);
+                    code = Some(language.to_string());
+                }
+                Event::End(Tag::CodeBlock(_)) => {
+                    blocks.push(Block {
+                        lists: lists.clone(),
+                        first,
+                        kind: BlockKind::Code {
+                            language: code.take().unwrap_or_default(),
+                            text: std::mem::take(&mut text),
+                        },
+                    });
+                    first = false;
+                }
+                Event::Text(code_text) if code.is_some() => text.push_str(&code_text),
+
+                // Links and images.
+                Event::Start(Tag::Link(kind, url, _)) | Event::Start(Tag::Image(kind, url, _)) => {
+                    link = Some((kind, url.to_string(), text.len()))
+                }
+                Event::End(Tag::Link(_, _, _)) => {
+                    if let Some((kind, url, start)) = link.take() {
+                        let label = text.split_off(start);
+                        text.push_str(&self.link(kind, &url, &label));
+                    }
+                }
+                Event::End(Tag::Image(_, _, _)) => {
+                    if let Some((_, url, start)) = link.take() {
+                        let alt = text.split_off(start);
+                        text.push_str(&self.image(&url, &alt));
+                    }
+                }
+
+                event => self.push_inline(&mut text, event),
+            }
+        }
+        flush(&mut blocks, &lists, &mut first, &mut text);
+
+        blocks
+    }
+
+    /// Appends the markup of an inline event.
+    fn push_inline(&mut self, text: &mut String, event: Event) {
+        let markup = match (self.syntax, event) {
+            (_, Event::Text(content)) => return text.push_str(&self.escape(&content)),
+            (_, Event::Code(code)) => return text.push_str(&self.code(&code)),
+            (_, Event::FootnoteReference(name)) => {
+                let reference = self.footnote_reference(&name);
+                return text.push_str(&reference);
+            }
+            (_, Event::SoftBreak) => " ",
+
+            // AsciiDoc. Unconstrained marks are used, as they work within words.
+            (Syntax::AsciiDoc, Event::Start(Tag::Emphasis))
+            | (Syntax::AsciiDoc, Event::End(Tag::Emphasis)) => "__",
+            (Syntax::AsciiDoc, Event::Start(Tag::Strong))
+            | (Syntax::AsciiDoc, Event::End(Tag::Strong)) => "**",
+            (Syntax::AsciiDoc, Event::Start(Tag::Strikethrough)) => "[.line-through]##",
+            (Syntax::AsciiDoc, Event::End(Tag::Strikethrough)) => "##",
+            (Syntax::AsciiDoc, Event::HardBreak) => " +\n",
+            (Syntax::AsciiDoc, Event::InlineHtml(html))
+            | (Syntax::AsciiDoc, Event::Html(html)) => {
+                return text.push_str(&format!("pass:[{}]", html.trim_end().replace(']', "\\]")));
+            }
+
+            // reStructuredText has no strikethrough, and raw HTML is dropped.
+            (Syntax::Rst, Event::Start(Tag::Emphasis)) | (Syntax::Rst, Event::End(Tag::Emphasis)) => {
+                "*"
+            }
+            (Syntax::Rst, Event::Start(Tag::Strong)) | (Syntax::Rst, Event::End(Tag::Strong)) => "**",
+            (Syntax::Rst, Event::HardBreak) => " ",
+
+            _ => "",
+        };
+
+        text.push_str(markup);
+    }
+
+    /// Escapes text so it isn't read as markup.
+    fn escape(&self, text: &str) -> String {
+        match self.syntax {
+            // Text with formatting marks or attribute references is passed
+            // through, which still escapes HTML.
+            Syntax::AsciiDoc if text.contains(|c| "*_`#^~+{}[]".contains(c)) => {
+                format!("pass:c[{}]", text.replace(']', "\\]"))
+            }
+            Syntax::AsciiDoc => text.to_string(),
+            Syntax::Rst => {
+                let mut escaped = String::with_capacity(text.len());
+                for c in text.chars() {
+                    if "\\*`_|".contains(c) {
+                        escaped.push('\\');
+                    }
+                    escaped.push(c);
+                }
+
+                escaped
+            }
+        }
+    }
+
+    fn code(&self, code: &str) -> String {
+        match self.syntax {
+            Syntax::AsciiDoc if code.contains('+') => format!("`pass:c[{}]`", code.replace(']', "\\]")),
+            Syntax::AsciiDoc => format!("`+{}+`", code),
+            Syntax::Rst => format!("``{}``", code),
+        }
+    }
+
+    fn link(&self, kind: LinkType, url: &str, label: &str) -> String {
+        match (self.syntax, kind) {
+            (Syntax::AsciiDoc, LinkType::Autolink) => format!("<{}>", url),
+            (Syntax::AsciiDoc, LinkType::Email) => format!("mailto:{}[]", url),
+            (Syntax::Rst, LinkType::Autolink) | (Syntax::Rst, LinkType::Email) => url.to_string(),
+            (Syntax::AsciiDoc, _) => asciidoc_link(url, label),
+            (Syntax::Rst, _) => rst_link(url, label),
+        }
+    }
+
+    fn image(&self, url: &str, alt: &str) -> String {
+        match self.syntax {
+            Syntax::AsciiDoc => format!("image:{}[{}]", url, alt.replace(']', "\\]")),
+            // Inline images need a substitution definition, so images are
+            // linked to instead.
+            Syntax::Rst => rst_link(url, alt),
+        }
+    }
+
+    fn footnote_reference(&mut self, name: &str) -> String {
+        let first = self.referenced.insert(name.to_string());
+
+        match self.syntax {
+            Syntax::AsciiDoc if !first => format!("footnote:fn-{}[]", name),
+            Syntax::AsciiDoc => {
+                let footnote = self.footnotes.iter().find(|footnote| footnote.name() == name);
+                let text = match footnote {
+                    Some(footnote) => self.inline(footnote.text()),
+                    None => String::new(),
+                };
+
+                format!("footnote:fn-{}[{}]", name, text.replace(']', "\\]"))
+            }
+            // The whitespace before a footnote reference is escaped, so it can
+            // directly follow a word.
+            Syntax::Rst => format!("\\ [{}]_", rst_footnote_label(name)),
+        }
+    }
+
+    /// Converts markdown to a single line of inline markup.
+    fn inline(&mut self, markdown: &str) -> String {
+        let paragraphs: Vec<String> = self
+            .blocks(markdown, Vec::new())
+            .into_iter()
+            .map(|block| match block.kind {
+                BlockKind::Paragraph(text) => text,
+                BlockKind::Code { text, .. } => self.code(text.trim()),
+            })
+            .collect();
+
+        paragraphs.join(" ")
+    }
+}
+
+fn asciidoc_link(url: &str, label: &str) -> String {
+    let label = label.replace(']', "\\]");
+
+    if url.contains("://") || url.starts_with("mailto:") {
+        format!("{}[{}]", url, label)
+    } else {
+        format!("link:{}[{}]", url, label)
+    }
+}
+
+fn rst_link(url: &str, label: &str) -> String {
+    if label.is_empty() {
+        format!("`<{}>`__", url)
+    } else {
+        format!("`{} <{}>`__", label, url)
+    }
+}
+
+/// The label of a reStructuredText footnote. Numeric footnotes keep their
+/// number, others are numbered automatically.
+fn rst_footnote_label(name: &str) -> String {
+    if name.bytes().all(|b| b.is_ascii_digit()) {
+        name.to_string()
+    } else {
+        format!("#{}", name)
+    }
+}
+
+/// The anchor of a release, e.g. `release-1.0.0`.
+fn anchor(release: &Release) -> String {
+    let version = match (release.version(), release.invalid_version()) {
+        (Some(version), _) => version.to_string(),
+        (None, Some(version)) => version.to_string(),
+        (None, None) => "unreleased".to_string(),
+    };
+    let slug: String = version
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || c == '.' || c == '_' => c.to_ascii_lowercase(),
+            _ => '-',
+        })
+        .collect();
+
+    format!("release-{}", slug)
+}
+
+/// The text of a release heading, with the version linked to the release.
+fn release_heading(converter: &Converter, release: &Release, options: &RenderOptions) -> String {
+    let locale = options.locale();
+    let version = match (release.version(), release.invalid_version()) {
+        (Some(version), _) => converter.escape(&version.to_string()),
+        (None, Some(version)) => converter.escape(version),
+        (None, None) => converter.escape(locale.unreleased()),
+    };
+
+    let mut heading = match (release.link(), *release.yanked(), converter.syntax) {
+        (Some(link), false, Syntax::AsciiDoc) => asciidoc_link(link, &version),
+        (Some(link), false, Syntax::Rst) => rst_link(link, &version),
+        _ => version,
+    };
+
+    if let (false, Some(date)) = (release.is_unreleased(), release.date()) {
+        let date = locale.format_date(date, options.date_format());
+        heading.push_str(&format!(" {} {}", options.separator(), date));
+    }
+
+    for label in release.labels() {
+        heading.push_str(&format!(" [{}]", converter.escape(label)));
+    }
+
+    if *release.yanked() && !release.is_unreleased() {
+        heading.push_str(&format!(" [{}]", converter.escape(locale.yanked())));
+    }
+
+    heading
+}
+
+/// The columns taken by text, counting wide East Asian characters twice.
+fn width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6 => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// Renders a changelog as an AsciiDoc document.
+pub(crate) struct AsciiDoc<'a> {
+    changelog: &'a Changelog<'a>,
+    options: &'a RenderOptions,
+}
+
+impl<'a> AsciiDoc<'a> {
+    pub(crate) fn new(changelog: &'a Changelog<'a>, options: &'a RenderOptions) -> Self {
+        Self { changelog, options }
+    }
+}
+
+impl<'a> fmt::Display for AsciiDoc<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let changelog = self.changelog;
+        let options = self.options;
+        let mut converter = Converter::new(Syntax::AsciiDoc, changelog.footnotes());
+
+        writeln!(fmt, "= {}", converter.escape(changelog.title()))?;
+        write_asciidoc_blocks(fmt, converter.blocks(changelog.description(), Vec::new()))?;
+
+        for release in changelog.releases() {
+            fmt.write_str("\n")?;
+            writeln!(fmt, "[[{}]]", anchor(release))?;
+            writeln!(fmt, "== {}", release_heading(&converter, release, options))?;
+
+            if let (true, Some(reason)) = (*release.yanked(), release.yank_reason()) {
+                write_asciidoc_blocks(fmt, converter.blocks(reason, Vec::new()))?;
+            }
+
+            for section in options.sections(release) {
+                let mut changes = release
+                    .changes()
+                    .iter()
+                    .filter(|change| change.kind() == section)
+                    .peekable();
+
+                if changes.peek().is_none() {
+                    continue;
+                }
+
+                fmt.write_str("\n")?;
+                writeln!(fmt, "=== {}", converter.escape(options.section_name(section)))?;
+                fmt.write_str("\n")?;
+
+                for change in changes {
+                    let blocks = converter.blocks(change.description(), vec![false]);
+                    write_asciidoc_blocks(fmt, blocks)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_asciidoc_blocks(fmt: &mut fmt::Formatter, blocks: Vec<Block>) -> fmt::Result {
+    for block in blocks {
+        let in_list = !block.lists.is_empty();
+
+        match (in_list, block.first) {
+            // Paragraphs outside of lists are separated by blank lines.
+            (false, _) => fmt.write_str("\n")?,
+            (true, true) => {
+                let marker = match block.lists.last() {
+                    Some(true) => ".",
+                    _ => "*",
+                };
+                write!(fmt, "{} ", marker.repeat(block.lists.len()))?;
+            }
+            // Further blocks of a list item are attached with a continuation.
+            (true, false) => fmt.write_str("+\n")?,
+        }
+
+        match block.kind {
+            BlockKind::Paragraph(text) => writeln!(fmt, "{}", text)?,
+            BlockKind::Code { language, text } => {
+                // A list item can't start with a delimited block.
+                if in_list && block.first {
+                    fmt.write_str("{empty}\n+\n")?;
+                }
+                if !language.is_empty() {
+                    writeln!(fmt, "[source,{}]", language)?;
+                }
+                write!(fmt, "----\n{}----\n", text)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders a changelog as a reStructuredText document.
+pub(crate) struct Rst<'a> {
+    changelog: &'a Changelog<'a>,
+    options: &'a RenderOptions,
+}
+
+impl<'a> Rst<'a> {
+    pub(crate) fn new(changelog: &'a Changelog<'a>, options: &'a RenderOptions) -> Self {
+        Self { changelog, options }
+    }
+}
+
+impl<'a> fmt::Display for Rst<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let changelog = self.changelog;
+        let options = self.options;
+        let mut converter = Converter::new(Syntax::Rst, changelog.footnotes());
+
+        let title = converter.escape(changelog.title());
+        let rule = "=".repeat(width(&title));
+        write!(fmt, "{}\n{}\n{}\n", rule, title, rule)?;
+        write_rst_blocks(fmt, converter.blocks(changelog.description(), Vec::new()))?;
+
+        for release in changelog.releases() {
+            let heading = release_heading(&converter, release, options);
+
+            fmt.write_str("\n")?;
+            writeln!(fmt, ".. _{}:", anchor(release))?;
+            fmt.write_str("\n")?;
+            writeln!(fmt, "{}\n{}", heading, "-".repeat(width(&heading)))?;
+
+            if let (true, Some(reason)) = (*release.yanked(), release.yank_reason()) {
+                write_rst_blocks(fmt, converter.blocks(reason, Vec::new()))?;
+            }
+
+            for section in options.sections(release) {
+                let mut changes = release
+                    .changes()
+                    .iter()
+                    .filter(|change| change.kind() == section)
+                    .peekable();
+
+                if changes.peek().is_none() {
+                    continue;
+                }
+
+                let name = converter.escape(options.section_name(section));
+                fmt.write_str("\n")?;
+                writeln!(fmt, "{}\n{}", name, "~".repeat(width(&name)))?;
+
+                for change in changes {
+                    let blocks = converter.blocks(change.description(), vec![false]);
+                    write_rst_blocks(fmt, blocks)?;
+                }
+            }
+        }
+
+        if !changelog.footnotes().is_empty() {
+            fmt.write_str("\n")?;
+        }
+
+        for footnote in changelog.footnotes() {
+            let text = converter.inline(footnote.text());
+            writeln!(fmt, ".. [{}] {}", rst_footnote_label(footnote.name()), text)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes blocks separated by blank lines, with the content of list items
+/// indented to line up with the text after their markers.
+fn write_rst_blocks(fmt: &mut fmt::Formatter, blocks: Vec<Block>) -> fmt::Result {
+    for block in blocks {
+        let marker = |ordered: &bool| if *ordered { "#. " } else { "- " };
+        let (parents, last) = match block.lists.split_last() {
+            Some((last, parents)) => (parents, Some(last)),
+            None => (&block.lists[..], None),
+        };
+
+        let mut indent: String = parents.iter().map(|ordered| " ".repeat(marker(ordered).len())).collect();
+        fmt.write_str("\n")?;
+
+        match (last, block.first) {
+            (Some(ordered), true) => {
+                write!(fmt, "{}{}", indent, marker(ordered))?;
+                indent.push_str(&" ".repeat(marker(ordered).len()));
+            }
+            (Some(ordered), false) => {
+                indent.push_str(&" ".repeat(marker(ordered).len()));
+                fmt.write_str(&indent)?;
+            }
+            (None, _) => (),
+        }
+
+        match block.kind {
+            BlockKind::Paragraph(text) => writeln!(fmt, "{}", text)?,
+            BlockKind::Code { language, text } => {
+                match language.as_str() {
+                    "" => fmt.write_str(".. code::\n\n")?,
+                    language => write!(fmt, ".. code:: {}\n\n", language)?,
+                }
+                for line in text.lines() {
+                    writeln!(fmt, "{}   {}", indent, line)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
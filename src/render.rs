@@ -0,0 +1,675 @@
+use crate::changelog::{Changelog, Release};
+use crate::config::Config;
+use crate::conventional::ConventionalOptions;
+use crate::debian::DebianOptions;
+use crate::feed::FeedOptions;
+use crate::locale::{Locale, EN};
+use crate::markdown::restyle_emphasis;
+use crate::rpm::RpmOptions;
+use crate::wrap::entry_lines;
+use derive_builder::Builder;
+use derive_getters::Getters;
+use err_derive::Error;
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use versions::Version;
+
+/// The sections of a release, in the order they are rendered by default.
+pub const DEFAULT_SECTION_ORDER: &[&str] =
+    &["Added", "Changed", "Deprecated", "Removed", "Fixed", "Security"];
+
+/// The format release dates are written with by default.
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The narrowest column change entries can be wrapped at.
+pub const MIN_WRAP_WIDTH: usize = 4;
+
+#[derive(Debug, Error)]
+pub enum RenderError {
+    #[error(
+        display = "invalid wrap width {}, entries can't be wrapped at less than {} columns",
+        _0,
+        MIN_WRAP_WIDTH
+    )]
+    InvalidWrapWidth(usize),
+    #[error(display = "invalid wrap mode: {}", _0)]
+    InvalidWrapMode(String),
+}
+
+/// How change entries are wrapped.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Wrap {
+    /// Reflow entries to fit within the given column.
+    At(usize),
+    /// Keep the line breaks entries were written with.
+    Preserve,
+    /// Write each paragraph of an entry on a single line.
+    Off,
+}
+
+impl FromStr for Wrap {
+    type Err = RenderError;
+
+    /// Parses a wrap column, `preserve`, or `off`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "preserve" => Ok(Wrap::Preserve),
+            "off" | "none" => Ok(Wrap::Off),
+            width => match width.parse::<usize>() {
+                Ok(width) if width < MIN_WRAP_WIDTH => Err(RenderError::InvalidWrapWidth(width)),
+                Ok(width) => Ok(Wrap::At(width)),
+                Err(_) => Err(RenderError::InvalidWrapMode(s.into())),
+            },
+        }
+    }
+}
+
+/// How the links of releases are written in a markdown changelog.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStyle {
+    /// `## [1.0.0] - 2019-06-20`, with `[1.0.0]: <link>` definitions at the
+    /// end of the changelog.
+    Reference,
+    /// `## [1.0.0](<link>) - 2019-06-20`.
+    Inline,
+}
+
+/// How the version of a release heading is written.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeadingStyle {
+    /// `## [1.0.0] - 2019-06-20`.
+    Bracketed,
+    /// `## 1.0.0 - 2019-06-20`. Release links are only kept with the inline
+    /// link style.
+    Plain,
+}
+
+/// The delimiters used for emphasis in change entries.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmphasisStyle {
+    /// `_emphasis_` and `__strong__`.
+    Underscore,
+    /// `*emphasis*` and `**strong**`.
+    Asterisk,
+}
+
+/// The line endings a changelog is written with.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+/// Options controlling how a changelog is rendered.
+#[derive(Debug, Clone, Builder, Getters, PartialEq)]
+pub struct RenderOptions {
+    /// The separator written between the version and date of a release heading.
+    #[builder(setter(into), default = "\"-\".into()")]
+    separator: String,
+    #[builder(default = "HeadingStyle::Bracketed")]
+    heading_style: HeadingStyle,
+    /// The `chrono` format release dates are written with.
+    #[builder(setter(into), default = "DEFAULT_DATE_FORMAT.into()")]
+    date_format: String,
+    /// The character change entries are listed with.
+    #[builder(default = "'-'")]
+    bullet: char,
+    /// The emphasis style of change entries, or `None` to keep the style they
+    /// were written with.
+    #[builder(default)]
+    emphasis: Option<EmphasisStyle>,
+    #[builder(default = "Wrap::At(80)")]
+    wrap: Wrap,
+    /// The order of the sections of a release. Sections that are not listed
+    /// are rendered after the others, in their default order.
+    #[builder(default = "default_section_order()")]
+    section_order: Vec<String>,
+    /// Whether sections are rendered in the order they first appear in a
+    /// release, rather than in `section_order`.
+    #[builder(default = "false")]
+    preserve_section_order: bool,
+    /// The names sections are rendered with, by change type, e.g. `Ajouté` for
+    /// `added`. Sections without a name use their change type.
+    #[builder(default)]
+    section_names: HashMap<String, String>,
+    #[builder(default = "LinkStyle::Reference")]
+    link_style: LinkStyle,
+    #[builder(default = "LineEnding::Lf")]
+    line_ending: LineEnding,
+    /// The locale of the words and dates of release headings.
+    #[builder(default = "&EN")]
+    #[getter(skip)]
+    locale: &'static Locale,
+    /// Whether text output is colored with ANSI escape codes.
+    #[builder(default = "false")]
+    color: bool,
+    /// The options of `debian/changelog` output.
+    #[builder(default)]
+    debian: DebianOptions,
+    /// The options of RPM `%changelog` output.
+    #[builder(default)]
+    rpm: RpmOptions,
+    /// The options of Atom and RSS feed output.
+    #[builder(default)]
+    feed: FeedOptions,
+    /// The options of conventional-changelog output.
+    #[builder(default)]
+    conventional: ConventionalOptions,
+}
+
+fn default_section_order() -> Vec<String> {
+    DEFAULT_SECTION_ORDER.iter().map(|s| s.to_string()).collect()
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptionsBuilder::default().build().unwrap()
+    }
+}
+
+impl RenderOptions {
+    /// Creates render options from the options set in a project configuration,
+    /// using the defaults for any that are unset.
+    pub fn from_config(config: &Config) -> Self {
+        let mut options = Self::default();
+
+        if let Some(locale) = config.locale().as_deref().and_then(Locale::find) {
+            options.set_locale(locale);
+        }
+
+        if let Some(separator) = config.separator() {
+            options.set_separator(separator.clone());
+        }
+
+        if let Some(heading_style) = config.heading_style() {
+            options.set_heading_style(heading_style.clone());
+        }
+
+        if let Some(date_format) = config.output_date_format() {
+            options.set_date_format(date_format.clone());
+        }
+
+        if let Some(bullet) = config.bullet() {
+            options.set_bullet(*bullet);
+        }
+
+        if let Some(emphasis) = config.emphasis() {
+            options.set_emphasis(Some(emphasis.clone()));
+        }
+
+        options.set_wrap(config.wrap_mode());
+
+        if let Some(section_order) = config.section_order() {
+            options.set_section_order(section_order.clone());
+        }
+
+        if let Some(preserve_section_order) = config.preserve_section_order() {
+            options.set_preserve_section_order(*preserve_section_order);
+        }
+
+        if let Some(section_names) = config.section_names() {
+            let mut names = options.section_names.clone();
+            names.extend(section_names.clone());
+            options.set_section_names(names);
+        }
+
+        if let Some(link_style) = config.link_style() {
+            options.set_link_style(link_style.clone());
+        }
+
+        if let Some(line_ending) = config.line_ending() {
+            options.set_line_ending(line_ending.clone());
+        }
+
+        if let Some(debian) = config.debian() {
+            options.set_debian(DebianOptions::from_config(debian));
+        }
+
+        if let Some(rpm) = config.rpm() {
+            options.set_rpm(RpmOptions::from_config(rpm));
+        }
+
+        if let Some(feed) = config.feed() {
+            options.set_feed(FeedOptions::from_config(feed));
+        }
+
+        if let Some(conventional) = config.conventional() {
+            options.set_conventional(ConventionalOptions::from_config(conventional));
+        }
+
+        options
+    }
+
+    pub fn set_separator(&mut self, separator: String) -> &mut Self {
+        self.separator = separator;
+        self
+    }
+
+    pub fn set_heading_style(&mut self, heading_style: HeadingStyle) -> &mut Self {
+        self.heading_style = heading_style;
+        self
+    }
+
+    pub fn set_date_format(&mut self, date_format: String) -> &mut Self {
+        self.date_format = date_format;
+        self
+    }
+
+    pub fn set_bullet(&mut self, bullet: char) -> &mut Self {
+        self.bullet = bullet;
+        self
+    }
+
+    pub fn set_emphasis(&mut self, emphasis: Option<EmphasisStyle>) -> &mut Self {
+        self.emphasis = emphasis;
+        self
+    }
+
+    pub fn set_wrap(&mut self, wrap: Wrap) -> &mut Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn set_section_order(&mut self, section_order: Vec<String>) -> &mut Self {
+        self.section_order = section_order;
+        self
+    }
+
+    pub fn set_preserve_section_order(&mut self, preserve_section_order: bool) -> &mut Self {
+        self.preserve_section_order = preserve_section_order;
+        self
+    }
+
+    /// Sets the names sections are rendered with, keyed by change type
+    /// regardless of case.
+    pub fn set_section_names(&mut self, section_names: HashMap<String, String>) -> &mut Self {
+        self.section_names = section_names
+            .into_iter()
+            .map(|(kind, name)| (kind.to_lowercase(), name))
+            .collect();
+        self
+    }
+
+    /// The locale of the words and dates of release headings.
+    pub fn locale(&self) -> &'static Locale {
+        self.locale
+    }
+
+    /// Sets the locale of release headings, along with the section names and
+    /// date format of the locale.
+    pub fn set_locale(&mut self, locale: &'static Locale) -> &mut Self {
+        self.set_section_names(locale.section_names());
+        self.set_date_format(locale.date_format().to_string());
+        self.locale = locale;
+        self
+    }
+
+    pub fn set_link_style(&mut self, link_style: LinkStyle) -> &mut Self {
+        self.link_style = link_style;
+        self
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) -> &mut Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    pub fn set_color(&mut self, color: bool) -> &mut Self {
+        self.color = color;
+        self
+    }
+
+    pub fn set_debian(&mut self, debian: DebianOptions) -> &mut Self {
+        self.debian = debian;
+        self
+    }
+
+    pub fn debian_mut(&mut self) -> &mut DebianOptions {
+        &mut self.debian
+    }
+
+    pub fn set_rpm(&mut self, rpm: RpmOptions) -> &mut Self {
+        self.rpm = rpm;
+        self
+    }
+
+    pub fn rpm_mut(&mut self) -> &mut RpmOptions {
+        &mut self.rpm
+    }
+
+    pub fn set_feed(&mut self, feed: FeedOptions) -> &mut Self {
+        self.feed = feed;
+        self
+    }
+
+    pub fn feed_mut(&mut self) -> &mut FeedOptions {
+        &mut self.feed
+    }
+
+    pub fn set_conventional(&mut self, conventional: ConventionalOptions) -> &mut Self {
+        self.conventional = conventional;
+        self
+    }
+
+    pub fn conventional_mut(&mut self) -> &mut ConventionalOptions {
+        &mut self.conventional
+    }
+
+    /// Checks that the options can be rendered with.
+    pub fn validate(&self) -> Result<(), RenderError> {
+        match self.wrap {
+            Wrap::At(width) if width < MIN_WRAP_WIDTH => Err(RenderError::InvalidWrapWidth(width)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The name a section is rendered with.
+    pub(crate) fn section_name<'a>(&'a self, kind: &'a str) -> &'a str {
+        self.section_names
+            .get(&kind.to_lowercase())
+            .map(String::as_str)
+            .unwrap_or(kind)
+    }
+
+    /// The sections of a release in the order they are rendered, including
+    /// any left out of the configured order.
+    pub(crate) fn sections(&self, release: &Release) -> Vec<&'static str> {
+        let mut sections: Vec<&'static str> = Vec::new();
+
+        if self.preserve_section_order {
+            for change in release.changes() {
+                if !sections.contains(&change.kind()) {
+                    sections.push(change.kind());
+                }
+            }
+
+            return sections;
+        }
+
+        for section in &self.section_order {
+            let known = DEFAULT_SECTION_ORDER
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(section));
+
+            if let Some(known) = known {
+                if !sections.contains(known) {
+                    sections.push(known);
+                }
+            }
+        }
+
+        for known in DEFAULT_SECTION_ORDER {
+            if !sections.contains(known) {
+                sections.push(known);
+            }
+        }
+
+        sections
+    }
+}
+
+/// Renders a release or changelog as markdown with the given options.
+pub(crate) struct Markdown<'a, T> {
+    value: &'a T,
+    options: &'a RenderOptions,
+}
+
+impl<'a, T> Markdown<'a, T> {
+    pub(crate) fn new(value: &'a T, options: &'a RenderOptions) -> Self {
+        Self { value, options }
+    }
+}
+
+impl<'a> fmt::Display for Markdown<'a, Release<'a>> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let release = self.value;
+        let options = self.options;
+
+        // Release Heading.
+        fmt.write_str("## ")?;
+
+        // Release Version.
+        let version: Option<&dyn fmt::Display> =
+            match (release.version(), release.invalid_version()) {
+                (Some(version), _) => Some(version),
+                (None, Some(version)) => Some(version),
+                (None, None) => None,
+            };
+        let link = match options.link_style {
+            LinkStyle::Inline => release.link().as_ref(),
+            LinkStyle::Reference => None,
+        };
+
+        let bracketed = options.heading_style == HeadingStyle::Bracketed;
+        let unreleased = options.locale.unreleased();
+
+        // Yanked releases keep their link, so it is restored when they are
+        // unyanked, and are only written plain without one.
+        let unlinked = release.link().is_none();
+        match (version, *release.yanked(), link) {
+            (Some(version), _, Some(link)) => write!(fmt, "[{}]({})", version, link)?,
+            (Some(version), true, None) if unlinked => write!(fmt, "{}", version)?,
+            (Some(version), _, None) if bracketed => write!(fmt, "[{}]", version)?,
+            (Some(version), _, None) => write!(fmt, "{}", version)?,
+            (None, _, Some(link)) => write!(fmt, "[{}]({})", unreleased, link)?,
+            (None, _, None) if bracketed => write!(fmt, "[{}]", unreleased)?,
+            (None, _, None) => fmt.write_str(unreleased)?,
+        }
+
+        if let (Some(_), Some(date)) = (version, release.date()) {
+            let date = options.locale.format_date(date, &options.date_format);
+            write!(fmt, " {} {}", options.separator, date)?;
+        }
+
+        // Release labels.
+        for label in release.labels() {
+            write!(fmt, " [{}]", label)?;
+        }
+
+        if *release.yanked() {
+            write!(fmt, " [{}]", options.locale.yanked())?;
+        }
+
+        fmt.write_str("\n")?;
+
+        // Yank reason. A release without one or any changes is still followed
+        // by a blank line, separating it from the next heading.
+        if let (true, Some(reason)) = (*release.yanked(), release.yank_reason()) {
+            write!(fmt, "{}\n\n", reason)?;
+        } else if release.changes().is_empty() {
+            fmt.write_str("\n")?;
+        }
+
+        write!(fmt, "{}", Changes::new(release, options))
+    }
+}
+
+/// Renders the sections of changes of a release as markdown.
+pub(crate) struct Changes<'a> {
+    release: &'a Release<'a>,
+    options: &'a RenderOptions,
+}
+
+impl<'a> Changes<'a> {
+    pub(crate) fn new(release: &'a Release<'a>, options: &'a RenderOptions) -> Self {
+        Self { release, options }
+    }
+}
+
+impl<'a> fmt::Display for Changes<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let release = self.release;
+        let options = self.options;
+
+        for section in options.sections(release) {
+            let mut changes = release
+                .changes()
+                .iter()
+                .filter(|change| change.kind() == section)
+                .peekable();
+
+            if changes.peek().is_none() {
+                continue;
+            }
+
+            writeln!(fmt, "### {}", options.section_name(section))?;
+
+            for change in changes {
+                let description = match &options.emphasis {
+                    Some(style) => restyle_emphasis(change.description(), style),
+                    None => Cow::Borrowed(change.description()),
+                };
+
+                write_entry(fmt, options.bullet, &description, &options.wrap)?;
+            }
+
+            fmt.write_str("\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_entry(
+    fmt: &mut fmt::Formatter,
+    bullet: char,
+    description: &str,
+    wrap: &Wrap,
+) -> fmt::Result {
+    write!(fmt, "{}", bullet)?;
+
+    // Continuation lines are indented to line up with the first.
+    for (index, line) in entry_lines(description, wrap).iter().enumerate() {
+        match (index, line.is_empty()) {
+            (0, _) => write!(fmt, " {}", line)?,
+            (_, true) => fmt.write_str("\n")?,
+            (_, false) => write!(fmt, "\n  {}", line)?,
+        }
+    }
+
+    fmt.write_str("\n")
+}
+
+impl<'a> fmt::Display for Markdown<'a, Changelog<'a>> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let changelog = self.value;
+
+        writeln!(fmt, "# {}", changelog.title())?;
+        fmt.write_str(changelog.description())?;
+
+        for release in changelog.releases() {
+            write!(fmt, "{}", Markdown::new(release, self.options))?;
+        }
+
+        if self.options.link_style == LinkStyle::Reference {
+            write_link_definitions(fmt, changelog.releases(), self.options)?;
+        }
+
+        // Footnotes are separated by blank lines, as a definition following
+        // another would otherwise continue it.
+        let mut separate = has_link_definitions(changelog.releases(), self.options);
+        for footnote in changelog.footnotes() {
+            if separate {
+                fmt.write_str("\n")?;
+            }
+            separate = true;
+
+            write!(fmt, "[^{}]:", footnote.name())?;
+            for (index, line) in footnote.text().lines().enumerate() {
+                match (index, line.is_empty()) {
+                    (0, _) => write!(fmt, " {}", line)?,
+                    (_, true) => fmt.write_str("\n")?,
+                    (_, false) => write!(fmt, "\n    {}", line)?,
+                }
+            }
+            fmt.write_str("\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn has_link_definitions(releases: &[Release], options: &RenderOptions) -> bool {
+    options.link_style == LinkStyle::Reference && releases.iter().any(|r| r.link().is_some())
+}
+
+fn write_link_definitions(
+    fmt: &mut fmt::Formatter,
+    releases: &[Release],
+    options: &RenderOptions,
+) -> fmt::Result {
+    let mut links: Vec<(&Version, &str)> = Vec::new();
+    let mut invalid_links: Vec<(&str, &str)> = Vec::new();
+    for release in releases {
+        match (release.version(), release.invalid_version(), release.link()) {
+            (Some(version), _, Some(link)) => links.push((version, link.as_ref())),
+            (None, Some(version), Some(link)) => {
+                invalid_links.push((version.as_ref(), link.as_ref()))
+            }
+            _ => (),
+        }
+    }
+
+    links.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    if let Some(release) = releases.first() {
+        if let (true, Some(link)) = (release.is_unreleased(), release.link()) {
+            writeln!(fmt, "[{}]: {}", options.locale.unreleased(), link)?;
+        }
+    }
+
+    for (version, link) in links {
+        writeln!(fmt, "[{}]: {}", version, link)?;
+    }
+
+    // Links of releases with unparseable versions can't be ordered, so they
+    // are kept in document order after the others.
+    for (version, link) in invalid_links {
+        writeln!(fmt, "[{}]: {}", version, link)?;
+    }
+
+    Ok(())
+}
+
+/// Converts the line endings of everything written through it.
+pub(crate) struct LineEndingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    line_ending: &'a LineEnding,
+}
+
+impl<'a, W: Write> LineEndingWriter<'a, W> {
+    pub(crate) fn new(inner: &'a mut W, line_ending: &'a LineEnding) -> Self {
+        Self { inner, line_ending }
+    }
+}
+
+impl<'a, W: Write> Write for LineEndingWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.line_ending {
+            LineEnding::Lf => self.inner.write_all(buf)?,
+            LineEnding::Crlf => {
+                for (index, line) in buf.split(|b| *b == b'\n').enumerate() {
+                    if index > 0 {
+                        self.inner.write_all(b"\r\n")?;
+                    }
+
+                    self.inner.write_all(line)?;
+                }
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,163 @@
+use crate::changelog::{Changelog, Release};
+use crate::config::RpmConfig;
+use crate::render::RenderOptions;
+use crate::wrap::entry_lines;
+use derive_builder::Builder;
+use derive_getters::Getters;
+use err_derive::Error;
+use std::fmt;
+use versions::Version;
+
+#[derive(Debug, Error)]
+pub enum RpmError {
+    #[error(
+        display = "the rpm packager is not set, set `packager` in the [rpm] section of the configuration or the RPM_PACKAGER environment variable"
+    )]
+    MissingPackager,
+    #[error(display = "release {} has no date, which rpm changelogs require", _0)]
+    MissingDate(String),
+}
+
+/// Options for rendering a changelog as the `%changelog` of an RPM spec file.
+#[derive(Debug, Clone, Builder, Getters, PartialEq)]
+pub struct RpmOptions {
+    /// The packager of the package, e.g. `Jane Doe <jane@example.com>`.
+    #[builder(setter(strip_option, into), default)]
+    packager: Option<String>,
+    /// The release appended to release versions, e.g. `1` for `1.2.0-1`.
+    #[builder(setter(into), default = "\"1\".into()")]
+    release: String,
+}
+
+impl Default for RpmOptions {
+    fn default() -> Self {
+        RpmOptionsBuilder::default().build().unwrap()
+    }
+}
+
+impl RpmOptions {
+    /// Creates RPM options from the `[rpm]` section of a project
+    /// configuration, using the defaults for any that are unset.
+    pub fn from_config(config: &RpmConfig) -> Self {
+        let mut options = Self::default();
+
+        if let Some(packager) = config.packager() {
+            options.set_packager(packager.clone());
+        }
+
+        if let Some(release) = config.release() {
+            options.set_release(release.clone());
+        }
+
+        options
+    }
+
+    pub fn set_packager(&mut self, packager: String) -> &mut Self {
+        self.packager = Some(packager);
+        self
+    }
+
+    pub fn set_release(&mut self, release: String) -> &mut Self {
+        self.release = release;
+        self
+    }
+
+    /// The version-release of a release. Pre-release versions sort before the
+    /// release, e.g. `1.0.0~rc.1-1`.
+    fn version(&self, version: &Version) -> String {
+        format!("{}-{}", version.to_string().replacen('-', "~", 1), self.release)
+    }
+}
+
+/// Renders the versioned releases of a changelog as the `%changelog` section
+/// of an RPM spec file.
+pub(crate) struct Rpm<'a> {
+    releases: Vec<&'a Release<'a>>,
+    options: &'a RenderOptions,
+    packager: &'a str,
+}
+
+impl<'a> Rpm<'a> {
+    /// Checks that the changelog can be rendered with the given options.
+    pub(crate) fn new(changelog: &'a Changelog, options: &'a RenderOptions) -> Result<Self, RpmError> {
+        let packager = options
+            .rpm()
+            .packager
+            .as_deref()
+            .ok_or(RpmError::MissingPackager)?;
+
+        // Unreleased sections and releases with invalid versions have no
+        // version to package.
+        let mut releases: Vec<&Release> = changelog
+            .releases()
+            .iter()
+            .filter(|release| release.version().is_some())
+            .collect();
+
+        for release in &releases {
+            if let (Some(version), None) = (release.version(), release.date()) {
+                return Err(RpmError::MissingDate(version.to_string()));
+            }
+        }
+
+        // rpmbuild and rpmlint expect entries newest first.
+        releases.sort_by(|a, b| b.date().cmp(a.date()));
+
+        Ok(Self {
+            releases,
+            options,
+            packager,
+        })
+    }
+}
+
+impl<'a> fmt::Display for Rpm<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("%changelog\n")?;
+
+        for (index, release) in self.releases.iter().enumerate() {
+            let (version, date) = match (release.version(), release.date()) {
+                (Some(version), Some(date)) => (version, date),
+                _ => continue,
+            };
+
+            if index > 0 {
+                fmt.write_str("\n")?;
+            }
+
+            writeln!(
+                fmt,
+                "* {} {} - {}",
+                date.format("%a %b %d %Y"),
+                self.packager,
+                self.options.rpm().version(version)
+            )?;
+
+            if release.changes().is_empty() {
+                writeln!(fmt, "- Update to {}", version)?;
+            }
+
+            for section in self.options.sections(release) {
+                let changes = release.changes().iter();
+
+                for change in changes.filter(|change| change.kind() == section) {
+                    // Macros are expanded in the changelog of a spec file,
+                    // and blank lines would end the entry.
+                    let description = change.description().replace('%', "%%");
+                    let lines = entry_lines(&description, self.options.wrap());
+                    let lines = lines.iter().filter(|line| !line.is_empty());
+
+                    for (index, line) in lines.enumerate() {
+                        if index == 0 {
+                            writeln!(fmt, "- {}", line)?;
+                        } else {
+                            writeln!(fmt, "  {}", line)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
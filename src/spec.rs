@@ -0,0 +1,80 @@
+use err_derive::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The Semantic Versioning spec the standard description links to.
+const SEMVER: &str = "https://semver.org/spec/v2.0.0.html";
+
+#[derive(Debug, Error)]
+pub enum SpecError {
+    #[error(display = "unknown Keep a Changelog version: {}", _0)]
+    UnknownVersion(String),
+}
+
+/// A version of the Keep a Changelog spec, as declared in the description of a
+/// changelog, e.g. `The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SpecVersion {
+    V1_0_0,
+    V1_1_0,
+}
+
+impl SpecVersion {
+    /// The latest version of the spec, used for new changelogs.
+    pub const LATEST: SpecVersion = SpecVersion::V1_1_0;
+
+    /// Finds the version of the spec a changelog description links to, in any
+    /// of its translations.
+    pub fn detect(description: &str) -> Option<Self> {
+        description
+            .match_indices("keepachangelog.com/")
+            .filter_map(|(index, prefix)| {
+                let path = &description[index + prefix.len()..];
+                let (_, path) = path.split_once('/')?;
+                let end = path
+                    .find(|c: char| !c.is_ascii_digit() && c != '.')
+                    .unwrap_or(path.len());
+
+                path[..end].parse().ok()
+            })
+            .next()
+    }
+
+    /// The standard description of a changelog following this version of the
+    /// spec.
+    pub fn description(&self) -> String {
+        format!(
+            "All notable changes to this project will be documented in this file.\n\n\
+             The format is based on [Keep a Changelog](https://keepachangelog.com/en/{}/),\n\
+             and this project adheres to [Semantic Versioning]({}).\n\n",
+            self, SEMVER
+        )
+    }
+
+    /// Whether an unreleased section marked as yanked is reported, following
+    /// the guidance on yanked releases from 1.1.0 on.
+    pub fn forbids_yanked_unreleased(&self) -> bool {
+        *self >= SpecVersion::V1_1_0
+    }
+}
+
+impl fmt::Display for SpecVersion {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(match self {
+            SpecVersion::V1_0_0 => "1.0.0",
+            SpecVersion::V1_1_0 => "1.1.0",
+        })
+    }
+}
+
+impl FromStr for SpecVersion {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1.0.0" => Ok(SpecVersion::V1_0_0),
+            "1.1.0" => Ok(SpecVersion::V1_1_0),
+            _ => Err(SpecError::UnknownVersion(s.to_string())),
+        }
+    }
+}
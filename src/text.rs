@@ -0,0 +1,197 @@
+use crate::changelog::{Changelog, Release};
+use crate::render::RenderOptions;
+use crate::wrap::indented_entry_lines;
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use std::fmt;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const STRIKETHROUGH: &str = "\x1b[9m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+
+/// The number of columns before the text of a change entry, `    - `.
+const ENTRY_INDENT: usize = 6;
+
+/// The color of the label of a section.
+fn section_color(kind: &str) -> &'static str {
+    match kind {
+        "Added" => GREEN,
+        "Changed" => YELLOW,
+        "Deprecated" => MAGENTA,
+        "Removed" | "Security" => RED,
+        _ => CYAN,
+    }
+}
+
+/// Renders a release or the releases of a changelog as plain text for reading
+/// in a terminal, colored if the options say so.
+pub(crate) struct Text<'a, T> {
+    value: &'a T,
+    options: &'a RenderOptions,
+}
+
+impl<'a, T> Text<'a, T> {
+    pub(crate) fn new(value: &'a T, options: &'a RenderOptions) -> Self {
+        Self { value, options }
+    }
+
+    /// Styles text with ANSI escape codes when output is colored.
+    fn paint(&self, styles: &[&str], text: &str) -> String {
+        if *self.options.color() {
+            format!("{}{}{}", styles.concat(), text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+impl<'a> fmt::Display for Text<'a, Release<'a>> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let release = self.value;
+        let options = self.options;
+        let locale = options.locale();
+        let yanked = *release.yanked() && !release.is_unreleased();
+
+        // Release heading.
+        let version = match (release.version(), release.invalid_version()) {
+            (Some(version), _) => version.to_string(),
+            (None, Some(version)) => version.to_string(),
+            (None, None) => locale.unreleased().to_string(),
+        };
+        let styles: &[&str] = if yanked { &[BOLD, STRIKETHROUGH] } else { &[BOLD] };
+        fmt.write_str(&self.paint(styles, &version))?;
+
+        if let (false, Some(date)) = (release.is_unreleased(), release.date()) {
+            let date = locale.format_date(date, options.date_format());
+            let date = format!("{} {}", options.separator(), date);
+            write!(fmt, " {}", self.paint(&[DIM], &date))?;
+        }
+
+        for label in release.labels() {
+            write!(fmt, " [{}]", label)?;
+        }
+
+        if yanked {
+            let label = format!("[{}]", locale.yanked());
+            write!(fmt, " {}", self.paint(&[BOLD, RED], &label))?;
+        }
+
+        fmt.write_str("\n")?;
+
+        if let (true, Some(reason)) = (yanked, release.yank_reason()) {
+            for line in plain_text(reason).lines() {
+                writeln!(fmt, "  {}", self.paint(&[DIM], line))?;
+            }
+        }
+
+        // Release changes.
+        for section in options.sections(release) {
+            let mut changes = release
+                .changes()
+                .iter()
+                .filter(|change| change.kind() == section)
+                .peekable();
+
+            if changes.peek().is_none() {
+                continue;
+            }
+
+            let name = options.section_name(section);
+            writeln!(fmt, "  {}", self.paint(&[BOLD, section_color(section)], name))?;
+
+            for change in changes {
+                let description = plain_text(change.description());
+                let lines = indented_entry_lines(&description, options.wrap(), ENTRY_INDENT);
+
+                for (index, line) in lines.iter().enumerate() {
+                    match (index, line.is_empty()) {
+                        (0, _) => writeln!(fmt, "    {} {}", options.bullet(), line)?,
+                        (_, true) => fmt.write_str("\n")?,
+                        (_, false) => writeln!(fmt, "      {}", line)?,
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for Text<'a, Changelog<'a>> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for (index, release) in self.value.releases().iter().enumerate() {
+            if index > 0 {
+                fmt.write_str("\n")?;
+            }
+
+            write!(fmt, "{}", Text::new(release, self.options))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Strips the markdown syntax of a change entry, keeping its paragraphs and
+/// nested lists.
+fn plain_text(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut lists: Vec<Option<usize>> = Vec::new();
+
+    let start_line = |text: &mut String| {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+    };
+
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_FOOTNOTES;
+    let mut events = Parser::new_ext(markdown, options).peekable();
+    while let Some(event) = events.next() {
+        match event {
+            Event::Text(content) | Event::Code(content) => text.push_str(&content),
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+            Event::FootnoteReference(name) => text.push_str(&format!("[{}]", name)),
+            // A list right after a paragraph is nested in the entry as a tight
+            // list, so it starts on the next line rather than after a blank one.
+            Event::End(Tag::Paragraph) => {
+                start_line(&mut text);
+                if !matches!(events.peek(), Some(Event::Start(Tag::List(_)))) {
+                    text.push('\n');
+                }
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                start_line(&mut text);
+                text.push('\n');
+            }
+
+            Event::Start(Tag::List(start)) => {
+                start_line(&mut text);
+                lists.push(start);
+            }
+            Event::End(Tag::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                start_line(&mut text);
+                text.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        text.push_str(&format!("{}. ", number));
+                        *number += 1;
+                    }
+                    _ => text.push_str("- "),
+                }
+            }
+            Event::End(Tag::Item) => start_line(&mut text),
+
+            _ => (),
+        }
+    }
+
+    text.trim_end().to_string()
+}
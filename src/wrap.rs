@@ -0,0 +1,313 @@
+use crate::render::Wrap;
+
+/// The number of columns taken by the bullet of a markdown change entry.
+const BULLET_WIDTH: usize = 2;
+
+/// A run of lines of a change entry that is wrapped as a whole, e.g. a
+/// paragraph or a nested list item.
+struct Segment<'a> {
+    /// The indentation of the segment within the entry.
+    indent: usize,
+    /// The list marker of a nested list item, e.g. `- ` or `1. `.
+    marker: &'a str,
+    words: Vec<&'a str>,
+    /// The hard line break ending the segment, either `\` or trailing spaces.
+    hard_break: &'a str,
+}
+
+/// A line of a change entry, after the bullet and indentation of the entry.
+enum Line<'a> {
+    Blank,
+    Segment(Segment<'a>),
+}
+
+/// Lays out the description of a change entry for the given wrap mode.
+///
+/// The returned lines don't include the bullet of the entry or the indentation
+/// of its continuation lines.
+pub(crate) fn entry_lines(description: &str, wrap: &Wrap) -> Vec<String> {
+    indented_entry_lines(description, wrap, BULLET_WIDTH)
+}
+
+/// Lays out the description of a change entry whose bullet takes
+/// `bullet_width` columns. Wrapped lines stay short of the wrap column.
+pub(crate) fn indented_entry_lines(
+    description: &str,
+    wrap: &Wrap,
+    bullet_width: usize,
+) -> Vec<String> {
+    let width = match wrap {
+        Wrap::Preserve => return description.lines().map(String::from).collect(),
+        Wrap::At(wrap_at) => Some(wrap_at.saturating_sub(bullet_width + 1).max(1)),
+        Wrap::Off => None,
+    };
+
+    let mut lines = Vec::new();
+    for line in segments(description) {
+        let segment = match line {
+            Line::Blank => {
+                lines.push(String::new());
+                continue;
+            }
+            Line::Segment(segment) => segment,
+        };
+
+        let prefix = " ".repeat(segment.indent);
+        let hanging = " ".repeat(segment.indent + segment.marker.len());
+        let width = width.map(|width| {
+            width
+                .saturating_sub(segment.indent + segment.marker.len())
+                .max(1)
+        });
+
+        let start = lines.len();
+        for (index, line) in fill(&segment.words, width).into_iter().enumerate() {
+            if index == 0 {
+                lines.push(format!("{}{}{}", prefix, segment.marker, line));
+            } else {
+                lines.push(format!("{}{}", hanging, line));
+            }
+        }
+
+        if lines.len() == start {
+            lines.push(format!("{}{}", prefix, segment.marker.trim_end()));
+        }
+
+        if let Some(last) = lines.last_mut() {
+            last.push_str(segment.hard_break);
+        }
+    }
+
+    // Blank lines only separate paragraphs.
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+
+    lines
+}
+
+/// Splits a description into paragraphs, lines ended by hard breaks, and
+/// nested list items.
+fn segments(description: &str) -> Vec<Line<'_>> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut open = false;
+
+    for line in description.lines() {
+        if line.trim().is_empty() {
+            if let Some(Line::Segment(_)) = lines.last() {
+                lines.push(Line::Blank);
+            }
+            open = false;
+            continue;
+        }
+
+        let indent = line.len() - line.trim_start().len();
+        let content = line.trim_start();
+        let (text, hard_break) = split_hard_break(content);
+
+        if let Some(marker) = list_marker(content) {
+            lines.push(Line::Segment(Segment {
+                indent,
+                marker,
+                words: words(text[marker.len()..].trim()),
+                hard_break,
+            }));
+        } else if let (true, Some(Line::Segment(segment))) = (open, lines.last_mut()) {
+            segment.words.extend(words(text));
+            segment.hard_break = hard_break;
+        } else {
+            // Lines following a hard break keep the indentation of the text
+            // of the line before them.
+            let indent = match lines.last() {
+                Some(Line::Segment(segment)) if !segment.hard_break.is_empty() => {
+                    segment.indent + segment.marker.len()
+                }
+                _ => indent,
+            };
+
+            lines.push(Line::Segment(Segment {
+                indent,
+                marker: "",
+                words: words(text),
+                hard_break,
+            }));
+        }
+
+        open = hard_break.is_empty();
+    }
+
+    lines
+}
+
+/// Splits the hard line break, a trailing backslash or two or more spaces, off
+/// the end of a line.
+fn split_hard_break(line: &str) -> (&str, &str) {
+    if line.ends_with('\\') && !line.ends_with("\\\\") {
+        (&line[..line.len() - 1], "\\")
+    } else if line.ends_with("  ") {
+        (line.trim_end(), "  ")
+    } else {
+        (line.trim_end(), "")
+    }
+}
+
+/// The list marker at the start of a line, including the space after it.
+fn list_marker(line: &str) -> Option<&str> {
+    if line.starts_with("- ") || line.starts_with("* ") || line.starts_with("+ ") {
+        return Some(&line[..2]);
+    }
+
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    let rest = &line[digits..];
+    if digits > 0 && digits < 10 && (rest.starts_with(". ") || rest.starts_with(") ")) {
+        return Some(&line[..digits + 2]);
+    }
+
+    None
+}
+
+/// Splits text into the words it may be wrapped between. Code spans, link
+/// destinations, autolinks and HTML tags are never split.
+fn words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        if c.is_whitespace() {
+            if let Some(start) = start.take() {
+                words.push(&text[start..index]);
+            }
+            continue;
+        }
+
+        if start.is_none() {
+            start = Some(index);
+        }
+
+        let closing = match c {
+            // Escaped characters never open anything.
+            '\\' => {
+                chars.next();
+                continue;
+            }
+            '`' => {
+                let mut fence = 1;
+                while let Some((_, '`')) = chars.peek() {
+                    chars.next();
+                    fence += 1;
+                }
+
+                Some("`".repeat(fence))
+            }
+            '(' if text[..index].ends_with(']') => Some(")".into()),
+            '<' if text[index + 1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') => {
+                Some(">".into())
+            }
+            _ => None,
+        };
+
+        // Skip to the end of the construct, if it is closed.
+        if let Some(closing) = closing {
+            let after = index + c.len_utf8() + if c == '`' { closing.len() - 1 } else { 0 };
+            if let Some(end) = find_closing(&text[after..], &closing) {
+                let end = after + end + closing.len();
+                while let Some((next, _)) = chars.peek() {
+                    if *next >= end {
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+        }
+    }
+
+    if let Some(start) = start {
+        words.push(&text[start..]);
+    }
+
+    words
+}
+
+/// Finds the end of a construct. A code span fence only matches a run of
+/// backticks of the same length.
+fn find_closing(text: &str, closing: &str) -> Option<usize> {
+    if !closing.starts_with('`') {
+        return text.find(closing);
+    }
+
+    let mut offset = 0;
+    while let Some(found) = text[offset..].find(closing) {
+        let found = offset + found;
+        let end = found + closing.len();
+        if !text[end..].starts_with('`') {
+            return Some(found);
+        }
+
+        offset = end + text[end..].bytes().take_while(|b| *b == b'`').count();
+    }
+
+    None
+}
+
+/// Fills lines with words greedily. Words longer than the width are put on a
+/// line of their own rather than broken.
+///
+/// A line never starts with a word that would make it a heading, list item or
+/// other block when parsed again, such as `#` or `1.`. The word before it is
+/// carried over to the next line with it, or it is kept on the current line.
+fn fill(words: &[&str], width: Option<usize>) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for word in words {
+        let current_width = line_width(&current);
+        let fits = width.map_or(true, |width| current_width + 1 + width_of(word) <= width);
+
+        if current.is_empty() || fits {
+            current.push(word);
+        } else if !starts_block(word) {
+            lines.push(current.join(" "));
+            current = vec![word];
+        } else if current.len() > 1 && !starts_block(current[current.len() - 1]) {
+            let previous = current.pop().unwrap_or_default();
+            lines.push(current.join(" "));
+            current = vec![previous, word];
+        } else {
+            current.push(word);
+        }
+    }
+
+    if !current.is_empty() {
+        lines.push(current.join(" "));
+    }
+
+    lines
+}
+
+fn width_of(word: &str) -> usize {
+    word.chars().count()
+}
+
+fn line_width(words: &[&str]) -> usize {
+    words.iter().map(|word| width_of(word)).sum::<usize>() + words.len().saturating_sub(1)
+}
+
+/// Whether a line starting with `word` would start a block, e.g. an ATX
+/// heading, a list item, a block quote, a code fence, a thematic break or a
+/// setext heading underline.
+fn starts_block(word: &str) -> bool {
+    let hashes = word.bytes().take_while(|b| *b == b'#').count();
+    let digits = word.bytes().take_while(u8::is_ascii_digit).count();
+
+    (1..=6).contains(&hashes) && hashes == word.len()
+        || matches!(word, "-" | "*" | "+")
+        || (1..=9).contains(&digits) && matches!(&word[digits..], "." | ")")
+        || word.starts_with('>')
+        || word.starts_with("```")
+        || word.starts_with("~~~")
+        || word.starts_with('<')
+        || word.bytes().all(|b| b == b'-')
+        || word.bytes().all(|b| b == b'=')
+        || word.len() >= 3 && (word.bytes().all(|b| b == b'*') || word.bytes().all(|b| b == b'_'))
+}
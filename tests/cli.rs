@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const CHANGELOG: &str = "# Changelog
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]
+### Fixed
+- Fix a bug that caused undefined behavior
+
+## [1.0.0] - 2019-06-20
+### Added
+- Add a cool new feature
+
+## [0.0.1] - 2019-05-31
+### Added
+- Add the initial features
+
+[Unreleased]: https://github.com/example/example/compare/v1.0.0...HEAD
+[1.0.0]: https://github.com/example/example/compare/v0.0.1...v1.0.0
+[0.0.1]: https://github.com/example/example/releases/tag/v0.0.1
+";
+
+/// Writes `contents` to a file named `name` in a directory of its own.
+fn fixture(name: &str, contents: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("clparse-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+
+    let path = directory.join(name);
+    fs::write(&path, contents).unwrap();
+
+    path
+}
+
+fn clparse(args: &[&str], path: &PathBuf) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_clparse"))
+        .args(args)
+        .arg(path)
+        .current_dir(path.parent().unwrap())
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn yanking_and_unyanking_a_release_restores_it() {
+    let path = fixture("yank.md", CHANGELOG);
+
+    let yanked = clparse(&["yank", "1.0.0", "--reason", "Broke the build"], &path);
+    assert!(yanked.status.success());
+
+    let contents = fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("## [1.0.0] - 2019-06-20 [YANKED]\nBroke the build\n"));
+    assert!(contents.contains("[1.0.0]: https://github.com/example/example/compare/v0.0.1...v1.0.0"));
+
+    let unyanked = clparse(&["unyank", "1.0.0"], &path);
+    assert!(unyanked.status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), CHANGELOG);
+}
+
+#[test]
+fn checking_a_formatted_json_changelog_succeeds() {
+    let markdown = fixture("check.md", CHANGELOG);
+    let json = clparse(&["-f", "json"], &markdown);
+    assert!(json.status.success());
+    assert!(json.stdout.ends_with(b"}\n"));
+
+    let path = fixture("check.json", std::str::from_utf8(&json.stdout).unwrap());
+    let checked = clparse(&["--check"], &path);
+    assert!(checked.status.success(), "{}", String::from_utf8_lossy(&checked.stdout));
+
+    let written = clparse(&["--write"], &path);
+    assert!(written.status.success());
+    assert_eq!(fs::read(&path).unwrap(), json.stdout);
+}
@@ -0,0 +1,109 @@
+use clparse::changelog::{Changelog, Diagnostic};
+use clparse::render::{RenderOptions, Wrap};
+use clparse::spec::SpecVersion;
+use clparse::{ChangelogParser, OutputFormat};
+
+#[test]
+fn verbatim_text_is_borrowed_from_the_buffer() {
+    let buffer = "# Changelog\n\n## [Unreleased]\n### Added\n- Add a **cool** new feature\n\n## [1.0.0] - 2019-06-20\n### Fixed\n- Fix the `--check` option\n\n[Unreleased]: https://example.com\n";
+    let changelog = ChangelogParser::default().parse_str(buffer).unwrap();
+    let source = buffer.as_bytes().as_ptr_range();
+
+    let descriptions = changelog
+        .releases()
+        .iter()
+        .flat_map(|release| release.changes())
+        .map(|change| change.description());
+    for description in descriptions {
+        assert!(
+            source.contains(&description.as_ptr()),
+            "{} was copied",
+            description
+        );
+    }
+    assert!(source.contains(&changelog.title().as_ptr()));
+
+    let owned = changelog.clone().into_owned();
+    assert_eq!(owned.releases(), changelog.releases());
+}
+
+#[test]
+fn wrapped_entries_never_start_a_line_with_a_block() {
+    let buffer = "# Changelog\n\n## [Unreleased]\n### Added\n- Support headings like # Title and ## Subtitle in entries\n- Accept lists such as 1. foo and 2) bar, - baz, + qux or * quux\n- Keep quotes > like this, fences ``` and ~~~, rules --- *** and ___, and underlines ===\n";
+    let parser = ChangelogParser::default();
+    let changelog = parser.parse_str(buffer).unwrap();
+
+    for width in 20..=80 {
+        let mut options = RenderOptions::default();
+        options.set_wrap(Wrap::At(width));
+
+        let mut output = Vec::new();
+        changelog
+            .write_to(&mut output, &OutputFormat::Markdown, &options)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let reparsed = parser.parse_str(&output).unwrap();
+
+        let words = |changelog: &Changelog| -> Vec<Vec<String>> {
+            changelog.releases()[0]
+                .changes()
+                .iter()
+                .map(|change| {
+                    change
+                        .description()
+                        .split_whitespace()
+                        .map(String::from)
+                        .collect()
+                })
+                .collect()
+        };
+        assert_eq!(
+            words(&reparsed),
+            words(&changelog),
+            "wrapped at {}:\n{}",
+            width,
+            output
+        );
+    }
+}
+
+/// A changelog following the given version of Keep a Changelog, with a yanked
+/// unreleased section, an undated release and releases out of order.
+fn changelog_following(spec: SpecVersion) -> String {
+    format!(
+        "# Changelog\n{}## [Unreleased] [YANKED]\n\n## [1.0.0]\n\n## [1.1.0] - 2019-06-20\n",
+        spec.description()
+    )
+}
+
+#[test]
+fn keep_a_changelog_1_0_0_allows_a_yanked_unreleased_section() {
+    let buffer = changelog_following(SpecVersion::V1_0_0);
+    let changelog = ChangelogParser::default().parse_str(&buffer).unwrap();
+
+    assert_eq!(changelog.spec_version(), Some(SpecVersion::V1_0_0));
+    assert_eq!(
+        changelog.diagnostics(),
+        vec![
+            Diagnostic::MissingDate("1.0.0".into()),
+            Diagnostic::UnorderedRelease("1.1.0".into()),
+        ]
+    );
+}
+
+#[test]
+fn keep_a_changelog_1_1_0_reports_a_yanked_unreleased_section() {
+    let buffer = changelog_following(SpecVersion::V1_1_0);
+    let changelog = ChangelogParser::default().parse_str(&buffer).unwrap();
+
+    assert_eq!(changelog.spec_version(), Some(SpecVersion::V1_1_0));
+    assert_eq!(
+        changelog.diagnostics(),
+        vec![
+            Diagnostic::YankedUnreleased,
+            Diagnostic::MissingDate("1.0.0".into()),
+            Diagnostic::UnorderedRelease("1.1.0".into()),
+        ]
+    );
+    assert!(changelog.to_string().contains("## [Unreleased] [YANKED]\n"));
+}